@@ -1,11 +1,11 @@
 #![allow(dead_code)]
 
-use crate::lexer::{Token, TokenKind};
+use crate::lexer::{Span, Token, TokenKind};
 
 #[derive(PartialEq)]
 pub(crate) enum Association {
-	LTR,
-	RTL,
+	Ltr,
+	Rtl,
 	None,
 }
 
@@ -14,6 +14,9 @@ impl Token {
 		match self.kind {
 			TokenKind::LParen
 			| TokenKind::RParen
+			| TokenKind::LBracket
+			| TokenKind::RBracket
+			| TokenKind::Period
 			| TokenKind::Multiply
 			| TokenKind::Divide
 			| TokenKind::Mod
@@ -29,9 +32,10 @@ impl Token {
 			| TokenKind::Xor
 			| TokenKind::BitOr
 			| TokenKind::And
-			| TokenKind::Or => Association::LTR,
+			| TokenKind::Or => Association::Ltr,
+			TokenKind::Range => Association::None,
 			TokenKind::Assign | TokenKind::Not | TokenKind::BitNot => {
-				Association::RTL
+				Association::Rtl
 			}
 			_ => Association::None,
 		}
@@ -39,20 +43,25 @@ impl Token {
 
 	pub(crate) fn get_precedence(&self) -> u8 {
 		match self.kind {
-			TokenKind::LParen | TokenKind::RParen => 12,
-			TokenKind::Not | TokenKind::BitNot => 11,
-			TokenKind::Multiply | TokenKind::Divide | TokenKind::Mod => 10,
-			TokenKind::Plus | TokenKind::Minus => 9,
+			TokenKind::LParen
+			| TokenKind::RParen
+			| TokenKind::LBracket
+			| TokenKind::RBracket
+			| TokenKind::Period => 13,
+			TokenKind::Not | TokenKind::BitNot => 12,
+			TokenKind::Multiply | TokenKind::Divide | TokenKind::Mod => 11,
+			TokenKind::Plus | TokenKind::Minus => 10,
 			TokenKind::Greater
 			| TokenKind::GreaterEq
 			| TokenKind::Less
-			| TokenKind::LessEq => 8,
-			TokenKind::Eq | TokenKind::NotEq => 7,
-			TokenKind::BitAnd => 6,
-			TokenKind::Xor => 5,
-			TokenKind::BitOr => 4,
-			TokenKind::And => 3,
-			TokenKind::Or => 2,
+			| TokenKind::LessEq => 9,
+			TokenKind::Eq | TokenKind::NotEq => 8,
+			TokenKind::BitAnd => 7,
+			TokenKind::Xor => 6,
+			TokenKind::BitOr => 5,
+			TokenKind::And => 4,
+			TokenKind::Or => 3,
+			TokenKind::Range => 2,
 			TokenKind::Assign => 1,
 			_ => 0,
 		}
@@ -63,10 +72,40 @@ impl Token {
 	pub(crate) fn is_un_op(&self) -> bool {
 		matches!(self.kind, TokenKind::Not | TokenKind::BitNot)
 	}
+
+	pub(crate) fn is_prefix_op(&self) -> bool {
+		self.is_un_op() || self.kind == TokenKind::Minus
+	}
+
+	pub(crate) fn is_postfix_op(&self) -> bool {
+		matches!(
+			self.kind,
+			TokenKind::LParen | TokenKind::LBracket | TokenKind::Period
+		)
+	}
+
+	pub(crate) fn is_bin_op(&self) -> bool {
+		self.is_op()
+			&& !self.is_un_op()
+			&& !matches!(
+				self.kind,
+				TokenKind::LParen
+					| TokenKind::RParen
+					| TokenKind::LBracket
+					| TokenKind::RBracket
+					| TokenKind::Period
+			)
+	}
 }
 
 #[derive(Clone, Debug, PartialEq)]
-pub enum Node {
+pub struct Node {
+	pub(crate) kind: NodeKind,
+	pub(crate) span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeKind {
 	// Expressions
 	BinExpr {
 		op: Token,
@@ -77,6 +116,34 @@ pub enum Node {
 		op: Token,
 		rhs: Box<Node>,
 	},
+	Call {
+		callee: Box<Node>,
+		args: Vec<Node>,
+	},
+	Member {
+		object: Box<Node>,
+		field: String,
+	},
+	Index {
+		object: Box<Node>,
+		index: Box<Node>,
+	},
+	// `object[start:end]`, where either bound may be left out
+	Slice {
+		object: Box<Node>,
+		start: Option<Box<Node>>,
+		end: Option<Box<Node>>,
+	},
+	Match {
+		subject: Box<Node>,
+		arms: Vec<MatchArm>,
+	},
+	If {
+		cond: Box<Node>,
+		body: Box<Node>,
+		// either a block or, for `else if`, another `If`
+		else_body: Option<Box<Node>>,
+	},
 
 	// Statements
 	Assign {
@@ -84,29 +151,103 @@ pub enum Node {
 		name: String,
 		value: Box<Node>,
 	},
+	SetIndex {
+		object: Box<Node>,
+		index: Box<Node>,
+		value: Box<Node>,
+	},
+	SetMember {
+		object: Box<Node>,
+		field: String,
+		value: Box<Node>,
+	},
+	Let {
+		name: String,
+		value: Box<Node>,
+		// the `///` comment right before the statement
+		doc: Option<String>,
+	},
+	Fun {
+		// anonymous functions have no name
+		name: Option<String>,
+		params: Vec<String>,
+		body: Box<Node>,
+		// only named functions can be documented
+		doc: Option<String>,
+	},
+	Return(Option<Box<Node>>),
+	Import {
+		names: Vec<String>,
+		// the file to import from, relative to the importing one; without
+		// it, every name is a built-in module
+		path: Option<String>,
+	},
+	While {
+		label: Option<String>,
+		cond: Box<Node>,
+		body: Box<Node>,
+	},
+	For {
+		label: Option<String>,
+		name: String,
+		iterable: Box<Node>,
+		body: Box<Node>,
+	},
+	// both target the innermost loop unless they name one
+	Break(Option<String>),
+	Continue(Option<String>),
 
 	// Blocks
-	// Block {
-	// 	stmts: Vec<Box<Node>>,
-	// 	state: HashMap<String, Node>,
-	// 	parent: Option<Box<Node>>,
-	// },
-	// BlockOpen,
-	// BlockClose,
+	Block(Vec<Node>),
 
 	// Literals
-	NumberLiteral(f64),
+	List(Vec<Node>),
+	Map(Vec<(String, Node)>),
+	IntLiteral(i64),
+	FloatLiteral(f64),
 	Ident(String),
 	BooleanLiteral(bool),
 	StringLiteral(String),
+	// `"a ${b} c"`, as the string literals and expressions to join
+	Interpolation(Vec<Node>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchArm {
+	// the arm is taken when any of its patterns matches
+	pub(crate) patterns: Vec<Pattern>,
+	pub(crate) guard: Option<Node>,
+	pub(crate) body: Node,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+	pub(crate) kind: PatternKind,
+	pub(crate) span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatternKind {
+	Int(i64),
+	Float(f64),
+	String(String),
+	Boolean(bool),
+	// matches anything, and binds it for the guard and body of the arm
+	Binding(String),
+	Wildcard,
 }
 
 impl Node {
+	pub(crate) fn new(kind: NodeKind, span: Span) -> Node {
+		Self { kind, span }
+	}
+
 	fn is_unary_op_ready_node(&self) -> bool {
 		matches!(
-			self, Self::UnaryExpr { op: _, rhs: _ }
-				| Self::BooleanLiteral(_)
-				| Self::Ident(_)
+			self.kind,
+			NodeKind::UnaryExpr { op: _, rhs: _ }
+				| NodeKind::BooleanLiteral(_)
+				| NodeKind::Ident(_)
 		)
 	}
 }
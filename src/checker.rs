@@ -0,0 +1,296 @@
+use std::fmt;
+
+use crate::{
+	ast::{MatchArm, Node, NodeKind, PatternKind},
+	lexer::Span,
+};
+
+#[derive(Debug, PartialEq)]
+pub struct CheckWarning {
+	pub(crate) kind: CheckWarningKind,
+	pub(crate) span: Span,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CheckWarningKind {
+	// the patterns left uncovered, when they can be named
+	NonExhaustive(Vec<String>),
+	UnreachableArm,
+	UnreachablePattern,
+}
+
+impl fmt::Display for CheckWarningKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::NonExhaustive(missing) if !missing.is_empty() => write!(
+				f,
+				"non-exhaustive match: {} not covered",
+				missing.join(" and ")
+			),
+			Self::NonExhaustive(_) => write!(f, "non-exhaustive match"),
+			Self::UnreachableArm => write!(f, "unreachable match arm"),
+			Self::UnreachablePattern => write!(f, "unreachable pattern"),
+		}
+	}
+}
+
+impl fmt::Display for CheckWarning {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} at {}:{}", self.kind, self.span.line, self.span.col)
+	}
+}
+
+// Looks for `match` expressions that can fail at runtime or contain arms that
+// never run. None of this stops a program from running, so every finding is a
+// warning.
+pub struct Checker {
+	warnings: Vec<CheckWarning>,
+}
+
+impl Checker {
+	pub fn new() -> Checker {
+		Self {
+			warnings: Vec::new(),
+		}
+	}
+
+	pub fn check(mut self, stmts: &[Node]) -> Vec<CheckWarning> {
+		for stmt in stmts {
+			self.node(stmt);
+		}
+
+		self.warnings
+	}
+
+	fn warn(&mut self, kind: CheckWarningKind, span: Span) {
+		self.warnings.push(CheckWarning { kind, span });
+	}
+
+	fn node(&mut self, node: &Node) {
+		match &node.kind {
+			NodeKind::Match { subject, arms } => {
+				self.node(subject);
+				for arm in arms {
+					if let Some(guard) = &arm.guard {
+						self.node(guard);
+					}
+					self.node(&arm.body);
+				}
+				// warnings about the whole match point at `match <subject>`
+				let span = Span {
+					end: subject.span.end,
+					..node.span
+				};
+				self.arms(arms, span);
+			}
+			NodeKind::BinExpr { lhs, rhs, .. } => {
+				self.node(lhs);
+				self.node(rhs);
+			}
+			NodeKind::UnaryExpr { rhs, .. } => self.node(rhs),
+			NodeKind::Call { callee, args } => {
+				self.node(callee);
+				for arg in args {
+					self.node(arg);
+				}
+			}
+			NodeKind::Member { object, .. } => self.node(object),
+			NodeKind::Index { object, index } => {
+				self.node(object);
+				self.node(index);
+			}
+			NodeKind::Slice { object, start, end } => {
+				self.node(object);
+				for bound in start.iter().chain(end) {
+					self.node(bound);
+				}
+			}
+			NodeKind::SetIndex {
+				object,
+				index,
+				value,
+			} => {
+				self.node(object);
+				self.node(index);
+				self.node(value);
+			}
+			NodeKind::SetMember { object, value, .. } => {
+				self.node(object);
+				self.node(value);
+			}
+			NodeKind::List(items) | NodeKind::Interpolation(items) => {
+				for item in items {
+					self.node(item);
+				}
+			}
+			NodeKind::Map(entries) => {
+				for (_, value) in entries {
+					self.node(value);
+				}
+			}
+			NodeKind::Assign { value, .. } | NodeKind::Let { value, .. } => {
+				self.node(value)
+			}
+			NodeKind::Fun { body, .. } => self.node(body),
+			NodeKind::Return(value) => {
+				if let Some(value) = value {
+					self.node(value);
+				}
+			}
+			NodeKind::If {
+				cond,
+				body,
+				else_body,
+			} => {
+				self.node(cond);
+				self.node(body);
+				if let Some(else_body) = else_body {
+					self.node(else_body);
+				}
+			}
+			NodeKind::Block(stmts) => {
+				for stmt in stmts {
+					self.node(stmt);
+				}
+			}
+			NodeKind::While { cond, body, .. } => {
+				self.node(cond);
+				self.node(body);
+			}
+			NodeKind::For { iterable, body, .. } => {
+				self.node(iterable);
+				self.node(body);
+			}
+			NodeKind::Import { .. }
+			| NodeKind::Break(_)
+			| NodeKind::Continue(_)
+			| NodeKind::IntLiteral(_)
+			| NodeKind::FloatLiteral(_)
+			| NodeKind::Ident(_)
+			| NodeKind::BooleanLiteral(_)
+			| NodeKind::StringLiteral(_) => {}
+		}
+	}
+
+	// Only booleans have a finite set of values, so any other match needs a
+	// `_` or binding arm to be exhaustive. Arms with a guard may always fall
+	// through, so they never cover anything.
+	fn arms(&mut self, arms: &[MatchArm], span: Span) {
+		let mut covered: Vec<&PatternKind> = Vec::new();
+		let mut exhaustive = false;
+
+		for arm in arms {
+			if exhaustive {
+				self.warn(
+					CheckWarningKind::UnreachableArm,
+					arm.patterns[0].span.to(arm.body.span),
+				);
+				continue;
+			}
+
+			for pattern in &arm.patterns {
+				if covered.contains(&&pattern.kind) {
+					self.warn(
+						CheckWarningKind::UnreachablePattern,
+						pattern.span,
+					);
+				}
+			}
+			if arm.guard.is_some() {
+				continue;
+			}
+
+			for pattern in &arm.patterns {
+				match pattern.kind {
+					PatternKind::Binding(_) | PatternKind::Wildcard => {
+						exhaustive = true
+					}
+					ref literal => covered.push(literal),
+				}
+			}
+			exhaustive |= [true, false]
+				.iter()
+				.all(|bool| covered.contains(&&PatternKind::Boolean(*bool)));
+		}
+
+		if exhaustive {
+			return;
+		}
+
+		let booleans = arms
+			.iter()
+			.flat_map(|arm| &arm.patterns)
+			.all(|pattern| matches!(pattern.kind, PatternKind::Boolean(_)));
+		let missing = if booleans && !arms.is_empty() {
+			[true, false]
+				.iter()
+				.filter(|bool| {
+					!covered.contains(&&PatternKind::Boolean(**bool))
+				})
+				.map(|bool| format!("`{}`", bool))
+				.collect()
+		} else {
+			Vec::new()
+		};
+
+		self.warn(CheckWarningKind::NonExhaustive(missing), span);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	use crate::{lexer::Lexer, parser::Parser};
+
+	fn check(input: &str) -> Vec<String> {
+		let tree = Parser::new(Lexer::new(input)).parse().unwrap();
+
+		Checker::new()
+			.check(&tree)
+			.iter()
+			.map(ToString::to_string)
+			.collect()
+	}
+
+	#[test]
+	fn check_exhaustive() {
+		assert_eq!(
+			check(
+				"match a { true -> 1, false -> 0 }\nmatch b { 1 | 2 -> 1, n \
+				 -> n }\nmatch c { x if x > 1 -> 1, _ -> 0 }"
+			),
+			Vec::<String>::new()
+		);
+	}
+
+	#[test]
+	fn check_non_exhaustive() {
+		assert_eq!(
+			check(
+				"match a { true -> 1 }\nmatch b { true -> 1, false if c -> 0 \
+				 }\nfun f(s) {\n\tmatch s { \"a\" -> 1, \"b\" -> 2 }\n}"
+			),
+			vec![
+				"non-exhaustive match: `false` not covered at 1:0",
+				"non-exhaustive match: `false` not covered at 2:0",
+				"non-exhaustive match at 4:1",
+			]
+		);
+	}
+
+	#[test]
+	fn check_unreachable() {
+		assert_eq!(
+			check(
+				"match a {\n\t1 -> 1\n\t2 | 1 -> 2\n\t_ -> 3\n\t4 -> \
+				 4\n}\nmatch b { true -> 1, false -> 0, _ -> 2 }"
+			),
+			vec![
+				"unreachable pattern at 3:5",
+				"unreachable match arm at 5:1",
+				"unreachable match arm at 7:33",
+			]
+		);
+	}
+}
@@ -0,0 +1,455 @@
+use std::{
+	fmt::Write,
+	io::{self, IsTerminal},
+	rc::Rc,
+};
+
+use crate::{
+	checker::{CheckWarning, CheckWarningKind},
+	eval::RuntimeError,
+	lexer::{LexError, Span},
+	module::Source,
+	parser::{ParseError, ParseErrorKind},
+	resolver::{ResolveError, ResolveErrorKind},
+};
+
+const TAB_WIDTH: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+	Error,
+	Warning,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+	pub(crate) severity: Severity,
+	pub(crate) message: String,
+	pub(crate) span: Span,
+	pub(crate) label: Option<String>,
+	pub(crate) notes: Vec<String>,
+	pub(crate) help: Option<String>,
+}
+
+impl Severity {
+	fn name(self) -> &'static str {
+		match self {
+			Self::Error => "error",
+			Self::Warning => "warning",
+		}
+	}
+
+	fn color(self) -> &'static str {
+		match self {
+			Self::Error => "\x1b[1;31m",
+			Self::Warning => "\x1b[1;33m",
+		}
+	}
+}
+
+struct Palette {
+	severity: &'static str,
+	gutter: &'static str,
+	bold: &'static str,
+	reset: &'static str,
+}
+
+impl Palette {
+	fn new(severity: Severity, color: bool) -> Palette {
+		if color {
+			Self {
+				severity: severity.color(),
+				gutter: "\x1b[1;34m",
+				bold: "\x1b[1m",
+				reset: "\x1b[0m",
+			}
+		} else {
+			Self {
+				severity: "",
+				gutter: "",
+				bold: "",
+				reset: "",
+			}
+		}
+	}
+}
+
+impl Diagnostic {
+	pub(crate) fn new(
+		severity: Severity,
+		message: impl Into<String>,
+		span: Span,
+	) -> Diagnostic {
+		Self {
+			severity,
+			message: message.into(),
+			span,
+			label: None,
+			notes: Vec::new(),
+			help: None,
+		}
+	}
+
+	pub(crate) fn error(message: impl Into<String>, span: Span) -> Diagnostic {
+		Self::new(Severity::Error, message, span)
+	}
+
+	pub(crate) fn warning(
+		message: impl Into<String>,
+		span: Span,
+	) -> Diagnostic {
+		Self::new(Severity::Warning, message, span)
+	}
+
+	pub(crate) fn with_label(mut self, label: impl Into<String>) -> Diagnostic {
+		self.label = Some(label.into());
+		self
+	}
+
+	pub(crate) fn with_note(mut self, note: impl Into<String>) -> Diagnostic {
+		self.notes.push(note.into());
+		self
+	}
+
+	pub(crate) fn with_help(mut self, help: impl Into<String>) -> Diagnostic {
+		self.help = Some(help.into());
+		self
+	}
+
+	pub fn render(&self, file: &str, source: &str, color: bool) -> String {
+		let palette = Palette::new(self.severity, color);
+		let Span { line, col, .. } = self.span;
+		let text = source.lines().nth(line.saturating_sub(1)).unwrap_or("");
+
+		// tabs are expanded so the underline lines up with the source text
+		let width_of = |str: &str| {
+			str.chars()
+				.map(|ch| if ch == '\t' { TAB_WIDTH } else { 1 })
+				.sum::<usize>()
+		};
+		let prefix =
+			text.char_indices().nth(col).map_or(text.len(), |(i, _)| i);
+		let offset = width_of(&text[..prefix]);
+
+		// spans running past the end of the line are cut off there
+		let line_start = self.span.start.saturating_sub(prefix);
+		let underline = source
+			.get(self.span.start..self.span.end.min(line_start + text.len()))
+			.map_or(1, |str| width_of(str).max(1));
+		let text = text.replace('\t', &" ".repeat(TAB_WIDTH));
+
+		let width = line.to_string().len();
+		let pad = " ".repeat(width);
+		let mut out = String::new();
+
+		let _ = writeln!(
+			out,
+			"{}{}{}: {}{}{}",
+			palette.severity,
+			self.severity.name(),
+			palette.reset,
+			palette.bold,
+			self.message,
+			palette.reset
+		);
+		let _ = writeln!(
+			out,
+			"{}{}-->{} {}:{}:{}",
+			pad,
+			palette.gutter,
+			palette.reset,
+			file,
+			line,
+			col + 1
+		);
+		let _ = writeln!(out, "{} {}|{}", pad, palette.gutter, palette.reset);
+		let _ = writeln!(
+			out,
+			"{}{} |{} {}",
+			palette.gutter, line, palette.reset, text
+		);
+		let _ = writeln!(
+			out,
+			"{} {}|{} {}{}{}{}{}",
+			pad,
+			palette.gutter,
+			palette.reset,
+			" ".repeat(offset),
+			palette.severity,
+			"^".repeat(underline),
+			self.label
+				.as_ref()
+				.map_or(String::new(), |label| format!(" {}", label)),
+			palette.reset
+		);
+
+		if !self.notes.is_empty() || self.help.is_some() {
+			let _ =
+				writeln!(out, "{} {}|{}", pad, palette.gutter, palette.reset);
+		}
+		for note in &self.notes {
+			let _ = writeln!(
+				out,
+				"{} {}={} {}note{}: {}",
+				pad,
+				palette.gutter,
+				palette.reset,
+				palette.bold,
+				palette.reset,
+				note
+			);
+		}
+		if let Some(help) = &self.help {
+			let _ = writeln!(
+				out,
+				"{} {}={} {}help{}: {}",
+				pad,
+				palette.gutter,
+				palette.reset,
+				palette.bold,
+				palette.reset,
+				help
+			);
+		}
+
+		out
+	}
+}
+
+impl From<&ParseError> for Diagnostic {
+	fn from(err: &ParseError) -> Diagnostic {
+		let diagnostic = match &err.kind {
+			ParseErrorKind::Expected { expected, found } => Diagnostic::error(
+				format!("expected {}, found {}", expected, found),
+				err.span,
+			)
+			.with_label(format!("expected {}", expected)),
+			ParseErrorKind::Lex(LexError::UnterminatedString(quotes)) => {
+				// raw strings close with the same quotes as any other
+				Diagnostic::error("unterminated string literal", err.span)
+					.with_label("string starts here")
+					.with_help(format!(
+						"add a closing `{}` to match the opening `{}`",
+						quotes.trim_start_matches('r'),
+						quotes
+					))
+			}
+			ParseErrorKind::Lex(LexError::UnterminatedComment) => {
+				Diagnostic::error("unterminated block comment", err.span)
+					.with_label("comment starts here")
+					.with_help(
+						"add a closing `*/`; block comments nest, so every \
+						 `/*` needs its own",
+					)
+			}
+			ParseErrorKind::Lex(LexError::UnexpectedChar(ch)) => {
+				Diagnostic::error(
+					format!("unexpected character {:?}", ch),
+					err.span,
+				)
+				.with_label("not valid here")
+			}
+			ParseErrorKind::Lex(LexError::InvalidEscape(escape)) => {
+				Diagnostic::error(
+					format!("invalid escape sequence `{}`", escape),
+					err.span,
+				)
+				.with_label("not a valid escape")
+				.with_help(
+					"valid escapes are `\\n`, `\\t`, `\\r`, `\\0`, `\\\\`, \
+					 `\\\"`, `\\$` and `\\u{...}`, or use a raw string like \
+					 `r\"...\"`",
+				)
+			}
+			ParseErrorKind::Lex(LexError::MalformedNumber(str)) => {
+				Diagnostic::error(
+					format!("malformed number literal `{}`", str),
+					err.span,
+				)
+				.with_label("not a valid number")
+				.with_help(
+					"numbers look like `42`, `1_000`, `1.5e-3`, `0xff`, \
+					 `0b1010` or `0o17`",
+				)
+			}
+			ParseErrorKind::Lex(LexError::NumberTooLarge(str)) => {
+				Diagnostic::error(
+					format!("number literal `{}` is too large", str),
+					err.span,
+				)
+				.with_label("does not fit in an int or float")
+			}
+		};
+
+		match err.recovery {
+			Some(Span { line, col, .. }) => diagnostic.with_note(format!(
+				"parsing resumed at {}:{}",
+				line,
+				col + 1
+			)),
+			None => diagnostic,
+		}
+	}
+}
+
+impl From<&ResolveError> for Diagnostic {
+	fn from(err: &ResolveError) -> Diagnostic {
+		let diagnostic = Diagnostic::error(err.kind.to_string(), err.span);
+
+		match &err.kind {
+			ResolveErrorKind::Undeclared(_) => {
+				diagnostic.with_label("not found in this scope")
+			}
+			ResolveErrorKind::UndeclaredAssign(name) => {
+				diagnostic.with_label("assigned here").with_help(format!(
+					"declare it first with `let {} = ...`",
+					name
+				))
+			}
+			ResolveErrorKind::ReturnOutsideFunction => {
+				diagnostic.with_label("cannot return from here")
+			}
+			ResolveErrorKind::OutsideLoop(keyword) => {
+				diagnostic.with_label(format!("cannot `{}` from here", keyword))
+			}
+			ResolveErrorKind::UndeclaredLabel(_) => {
+				diagnostic.with_label("no enclosing loop has this label")
+			}
+		}
+	}
+}
+
+impl From<&CheckWarning> for Diagnostic {
+	fn from(warning: &CheckWarning) -> Diagnostic {
+		let diagnostic =
+			Diagnostic::warning(warning.kind.to_string(), warning.span);
+
+		match &warning.kind {
+			CheckWarningKind::NonExhaustive(_) => diagnostic
+				.with_label("some values are not matched by any arm")
+				.with_help(
+					"add a `_ -> ...` arm to handle the remaining values",
+				),
+			CheckWarningKind::UnreachableArm => diagnostic
+				.with_label("never runs")
+				.with_note("an earlier arm already matches every value"),
+			CheckWarningKind::UnreachablePattern => {
+				diagnostic.with_label("already matched by an earlier arm")
+			}
+		}
+	}
+}
+
+impl From<&RuntimeError> for Diagnostic {
+	fn from(err: &RuntimeError) -> Diagnostic {
+		Diagnostic::error(err.message.clone(), err.span)
+	}
+}
+
+// colors are only used when the output ends up on a terminal
+pub fn use_color() -> bool {
+	io::stdout().is_terminal() && io::stderr().is_terminal()
+}
+
+pub fn emit(
+	diagnostics: impl IntoIterator<Item = Diagnostic>,
+	file: &str,
+	source: &str,
+) {
+	let color = use_color();
+	for diagnostic in diagnostics {
+		eprintln!("{}", diagnostic.render(file, source, color));
+	}
+}
+
+// diagnostics from files other than the one being run, like imported
+// modules, each shown against its own file
+pub fn emit_sourced<'a>(
+	diagnostics: impl IntoIterator<Item = &'a (Rc<Source>, Diagnostic)>,
+) {
+	let color = use_color();
+	for (source, diagnostic) in diagnostics {
+		eprintln!("{}", diagnostic.render(&source.name, &source.text, color));
+	}
+}
+
+// Runtime errors can happen inside an imported module, so they are shown
+// against the file they came from rather than the one that was run.
+pub fn emit_runtime(err: &RuntimeError, file: &str, source: &str) {
+	let color = use_color();
+	emit_sourced(&err.related);
+
+	let (file, source) = match &err.source {
+		Some(origin) => (origin.name.as_str(), origin.text.as_str()),
+		None => (file, source),
+	};
+	eprintln!("{}", Diagnostic::from(err).render(file, source, color));
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	use crate::{lexer::Lexer, parser::Parser};
+
+	#[test]
+	fn render_parse_error() {
+		let source = "let a = 1\nif a {\n\tlet b = (a + 2;\n}";
+		let errors = Parser::new(Lexer::new(source)).parse().unwrap_err();
+
+		assert_eq!(
+			Diagnostic::from(&errors[0]).render("test.dt", source, false),
+			[
+				"error: expected `)`, found end of statement",
+				" --> test.dt:3:16",
+				"  |",
+				"3 |     let b = (a + 2;",
+				"  |                   ^ expected `)`",
+				"  |",
+				"  = note: parsing resumed at 4:1",
+				"",
+			]
+			.join("\n")
+		)
+	}
+
+	#[test]
+	fn render_help() {
+		let source = "print(\"hi)";
+		let errors = Parser::new(Lexer::new(source)).parse().unwrap_err();
+
+		assert_eq!(
+			Diagnostic::from(&errors[0]).render("<repl>", source, false),
+			[
+				"error: unterminated string literal",
+				" --> <repl>:1:7",
+				"  |",
+				"1 | print(\"hi)",
+				"  |       ^^^^ string starts here",
+				"  |",
+				"  = help: add a closing `\"` to match the opening `\"`",
+				"",
+			]
+			.join("\n")
+		);
+
+		let source = "let a = \"\"\"one\ntwo";
+		let errors = Parser::new(Lexer::new(source)).parse().unwrap_err();
+
+		assert_eq!(
+			Diagnostic::from(&errors[0]).render("<repl>", source, false),
+			[
+				"error: unterminated string literal",
+				" --> <repl>:1:9",
+				"  |",
+				"1 | let a = \"\"\"one",
+				"  |         ^^^^^^ string starts here",
+				"  |",
+				"  = help: add a closing `\"\"\"` to match the opening \
+				 `\"\"\"`",
+				"",
+			]
+			.join("\n")
+		)
+	}
+}
@@ -0,0 +1,59 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::eval::NodeResult;
+
+pub type Env = Rc<RefCell<Environment>>;
+
+// The bindings of one scope. Lookups that miss walk up to the enclosing
+// scope, so inner blocks see every variable declared around them.
+#[derive(Debug, Default)]
+pub struct Environment {
+	values: HashMap<String, NodeResult>,
+	parent: Option<Env>,
+}
+
+impl Environment {
+	pub(crate) fn global() -> Env { Rc::new(RefCell::new(Self::default())) }
+
+	pub(crate) fn child(parent: &Env) -> Env {
+		Rc::new(RefCell::new(Self {
+			values: HashMap::new(),
+			parent: Some(Rc::clone(parent)),
+		}))
+	}
+
+	// `let` always binds in the innermost scope, shadowing any binding of the
+	// same name further out
+	pub(crate) fn define(
+		&mut self,
+		name: impl Into<String>,
+		value: NodeResult,
+	) {
+		self.values.insert(name.into(), value);
+	}
+
+	pub(crate) fn get(&self, name: &str) -> Option<NodeResult> {
+		match self.values.get(name) {
+			Some(value) => Some(value.clone()),
+			None => self.parent.as_ref()?.borrow().get(name),
+		}
+	}
+
+	// updates the nearest binding, returning false if there is none
+	pub(crate) fn assign(&mut self, name: &str, value: NodeResult) -> bool {
+		match self.values.get_mut(name) {
+			Some(slot) => {
+				*slot = value;
+				true
+			}
+			None => match &self.parent {
+				Some(parent) => parent.borrow_mut().assign(name, value),
+				None => false,
+			},
+		}
+	}
+
+	pub(crate) fn names(&self) -> impl Iterator<Item = &String> {
+		self.values.keys()
+	}
+}
@@ -0,0 +1,1670 @@
+use std::{
+	cell::RefCell,
+	cmp::Ordering,
+	collections::{BTreeMap, HashMap},
+	fmt, fs, iter, mem, ops,
+	path::{Path, PathBuf},
+	rc::Rc,
+};
+
+use crate::{
+	ast::{MatchArm, Node, NodeKind, PatternKind},
+	diagnostics::Diagnostic,
+	environment::{Env, Environment},
+	lexer::{Span, Token, TokenKind},
+	module::{self, Source},
+	stdlib,
+};
+
+#[derive(Clone, Debug)]
+pub enum NodeResult {
+	Int(i64),
+	Float(f64),
+	Boolean(bool),
+	String(String),
+	Nil,
+	Function(Rc<Function>),
+	Native(Rc<Native>),
+	List(Rc<RefCell<Vec<NodeResult>>>),
+	Map(Rc<RefCell<BTreeMap<String, NodeResult>>>),
+	// `start..end`, excluding `end`
+	Range(i64, i64),
+}
+
+// Calls nested deeper than this are reported instead of overflowing the stack
+// of the interpreter itself, which runs on a thread with `STACK_SIZE` bytes of
+// stack. How much stack a call takes depends on the build and on the function
+// body, so calls are also refused once less than `STACK_RESERVE` bytes are
+// left, which is far more than any single call uses.
+const MAX_CALL_DEPTH: usize = 1000;
+pub(crate) const STACK_SIZE: usize = 256 * 1024 * 1024;
+const STACK_RESERVE: usize = 16 * 1024 * 1024;
+
+#[derive(Debug)]
+pub struct Function {
+	pub(crate) name: Option<String>,
+	pub(crate) params: Vec<String>,
+	pub(crate) body: Node,
+	// the scope the function was created in, shared rather than copied so
+	// the function sees later changes to it
+	pub(crate) closure: Env,
+	pub(crate) source: Rc<Source>,
+}
+
+// a function implemented in Rust
+#[derive(Clone, Debug)]
+pub struct Native {
+	pub(crate) name: &'static str,
+	pub(crate) arity: usize,
+	pub(crate) fun: NativeFn,
+}
+
+// natives get their arguments already checked against their arity, and their
+// errors are reported at the call
+pub(crate) type NativeFn = fn(&[NodeResult]) -> Result<NodeResult, String>;
+
+#[derive(Debug, PartialEq)]
+pub struct RuntimeError {
+	pub(crate) message: String,
+	pub(crate) span: Span,
+	// the file the error happened in, filled in as it leaves that file's code
+	pub(crate) source: Option<Rc<Source>>,
+	// errors in another file that caused this one, like a broken import
+	pub(crate) related: Vec<(Rc<Source>, Diagnostic)>,
+}
+
+// `return`, `break` and `continue` leave every enclosing construct up to the
+// call or loop they target, so they travel up the evaluator the same way
+// errors do
+#[derive(Debug)]
+enum Unwind {
+	Error(RuntimeError),
+	Return(NodeResult),
+	Break(Option<String>),
+	Continue(Option<String>),
+}
+
+type OpResult = Result<NodeResult, String>;
+
+// the operands of arithmetic, once ints are promoted to match floats
+enum Numbers {
+	Int(i64, i64),
+	Float(f64, f64),
+}
+
+type EvalResult = Result<NodeResult, Unwind>;
+
+// roughly where the top of the stack is right now
+fn stack_address() -> usize {
+	let marker = 0u8;
+	&marker as *const u8 as usize
+}
+
+impl NodeResult {
+	pub(crate) fn type_name(&self) -> &'static str {
+		match self {
+			Self::Int(_) => "int",
+			Self::Float(_) => "float",
+			Self::Boolean(_) => "boolean",
+			Self::String(_) => "string",
+			Self::Nil => "nil",
+			Self::Function(_) | Self::Native(_) => "function",
+			Self::List(_) => "list",
+			Self::Map(_) => "map",
+			Self::Range(..) => "range",
+		}
+	}
+
+	// Both sides of an arithmetic operator, as ints when both are ints and
+	// as floats when either is a float.
+	fn numbers(&self, rhs: &Self) -> Option<Numbers> {
+		match (self, rhs) {
+			(Self::Int(lhs), Self::Int(rhs)) => Some(Numbers::Int(*lhs, *rhs)),
+			(Self::Int(lhs), Self::Float(rhs)) => {
+				Some(Numbers::Float(*lhs as f64, *rhs))
+			}
+			(Self::Float(lhs), Self::Int(rhs)) => {
+				Some(Numbers::Float(*lhs, *rhs as f64))
+			}
+			(Self::Float(lhs), Self::Float(rhs)) => {
+				Some(Numbers::Float(*lhs, *rhs))
+			}
+			_ => None,
+		}
+	}
+
+	// applies an integer operator that may not fit in an i64
+	fn checked(op: &str, lhs: i64, rhs: i64, result: Option<i64>) -> OpResult {
+		result.map(Self::Int).ok_or_else(|| {
+			format!(
+				"integer overflow: {} {} {} does not fit in an int",
+				lhs, op, rhs
+			)
+		})
+	}
+
+	// `None` when the quotient overflows, as `i64::MIN / -1` does
+	fn floor_div(lhs: i64, rhs: i64) -> Option<i64> {
+		let quotient = lhs.checked_div(rhs)?;
+		if lhs % rhs != 0 && (lhs < 0) != (rhs < 0) {
+			Some(quotient - 1)
+		} else {
+			Some(quotient)
+		}
+	}
+
+	fn floor_rem(lhs: i64, rhs: i64) -> i64 {
+		let remainder = lhs.wrapping_rem(rhs);
+		if remainder != 0 && (remainder < 0) != (rhs < 0) {
+			remainder + rhs
+		} else {
+			remainder
+		}
+	}
+
+	// bitwise operators only make sense for ints
+	fn bits(op: &str, lhs: &Self, rhs: &Self) -> Result<(i64, i64), String> {
+		match (lhs, rhs) {
+			(Self::Int(lhs), Self::Int(rhs)) => Ok((*lhs, *rhs)),
+			_ => Err(Self::mismatch(op, lhs, rhs)),
+		}
+	}
+
+	fn mismatch(op: &str, lhs: &Self, rhs: &Self) -> String {
+		format!(
+			"cannot apply `{}` to {} and {}",
+			op,
+			lhs.type_name(),
+			rhs.type_name()
+		)
+	}
+
+	pub(crate) fn compare(
+		&self,
+		rhs: &Self,
+		op: &str,
+	) -> Result<Ordering, String> {
+		let ordering = match (self.numbers(rhs), self, rhs) {
+			(Some(Numbers::Int(lhs, rhs)), ..) => Some(lhs.cmp(&rhs)),
+			(Some(Numbers::Float(lhs, rhs)), ..) => lhs.partial_cmp(&rhs),
+			(_, Self::String(lhs), Self::String(rhs)) => Some(lhs.cmp(rhs)),
+			_ => None,
+		};
+
+		ordering.ok_or_else(|| Self::mismatch(op, self, rhs))
+	}
+
+	// the number of items in lists and characters in strings
+	pub(crate) fn length(&self) -> Option<usize> {
+		match self {
+			Self::List(list) => Some(list.borrow().len()),
+			Self::String(str) => Some(str.chars().count()),
+			_ => None,
+		}
+	}
+
+	// where `self` points into a sequence of `len` items, with negative
+	// indices counting from the end
+	fn position(&self, len: usize) -> Result<i64, String> {
+		match self {
+			Self::Int(index) if *index < 0 => Ok(index + len as i64),
+			Self::Int(index) => Ok(*index),
+			_ => {
+				Err(format!("index must be an int, found {}", self.type_name()))
+			}
+		}
+	}
+
+	fn element(&self, len: usize) -> Result<usize, String> {
+		match self.position(len)? {
+			index if (0..len as i64).contains(&index) => Ok(index as usize),
+			_ => Err(format!("index {} out of range for length {}", self, len)),
+		}
+	}
+
+	// slice bounds may also point just past the last item
+	fn boundary(&self, len: usize) -> Result<usize, String> {
+		match self.position(len)? {
+			index if (0..=len as i64).contains(&index) => Ok(index as usize),
+			_ => Err(format!(
+				"slice bound {} out of range for length {}",
+				self, len
+			)),
+		}
+	}
+
+	fn range(self, rhs: Self) -> OpResult {
+		match (&self, &rhs) {
+			(Self::Int(start), Self::Int(end)) => Ok(Self::Range(*start, *end)),
+			_ => Err(Self::mismatch("..", &self, &rhs)),
+		}
+	}
+
+	// the values a `for` loop visits: the numbers in a range, the items of a
+	// list, the characters of a string and the keys of a map
+	fn iterate(&self) -> Result<Box<dyn Iterator<Item = Self>>, String> {
+		Ok(match self {
+			Self::Range(start, end) => Box::new((*start..*end).map(Self::Int)),
+			Self::List(list) => Box::new(list.borrow().clone().into_iter()),
+			Self::String(str) => Box::new(
+				str.chars()
+					.map(|ch| Self::String(ch.to_string()))
+					.collect::<Vec<_>>()
+					.into_iter(),
+			),
+			Self::Map(map) => Box::new(
+				map.borrow()
+					.keys()
+					.map(|key| Self::String(key.clone()))
+					.collect::<Vec<_>>()
+					.into_iter(),
+			),
+			_ => {
+				return Err(format!("cannot iterate over {}", self.type_name()))
+			}
+		})
+	}
+
+	fn matches(&self, pattern: &PatternKind) -> bool {
+		match (pattern, self) {
+			// numbers match whichever kind of number they equal
+			(PatternKind::Int(lhs), rhs) => Self::Int(*lhs) == *rhs,
+			(PatternKind::Float(lhs), rhs) => Self::Float(*lhs) == *rhs,
+			(PatternKind::String(lhs), Self::String(rhs)) => lhs == rhs,
+			(PatternKind::Boolean(lhs), Self::Boolean(rhs)) => lhs == rhs,
+			(PatternKind::Binding(_), _) | (PatternKind::Wildcard, _) => true,
+			_ => false,
+		}
+	}
+}
+
+impl PartialEq for NodeResult {
+	fn eq(&self, other: &Self) -> bool {
+		match (self, other) {
+			(Self::Int(_) | Self::Float(_), Self::Int(_) | Self::Float(_)) => {
+				self.compare(other, "==") == Ok(Ordering::Equal)
+			}
+			(Self::Boolean(lhs), Self::Boolean(rhs)) => lhs == rhs,
+			(Self::String(lhs), Self::String(rhs)) => lhs == rhs,
+			(Self::Nil, Self::Nil) => true,
+			(Self::Function(lhs), Self::Function(rhs)) => Rc::ptr_eq(lhs, rhs),
+			(Self::Native(lhs), Self::Native(rhs)) => Rc::ptr_eq(lhs, rhs),
+			(Self::List(lhs), Self::List(rhs)) => {
+				*lhs.borrow() == *rhs.borrow()
+			}
+			(Self::Map(lhs), Self::Map(rhs)) => *lhs.borrow() == *rhs.borrow(),
+			(
+				Self::Range(lhs_start, lhs_end),
+				Self::Range(rhs_start, rhs_end),
+			) => lhs_start == rhs_start && lhs_end == rhs_end,
+			_ => false,
+		}
+	}
+}
+
+impl fmt::Display for NodeResult {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Int(num) => write!(f, "{}", num),
+			// floats always show a fraction or exponent, unlike ints
+			Self::Float(num) => write!(f, "{:?}", num),
+			Self::Boolean(bool) => write!(f, "{}", bool),
+			Self::String(str) => write!(f, "{}", str),
+			Self::Nil => write!(f, "nil"),
+			Self::Function(fun) => match &fun.name {
+				Some(name) => write!(f, "<fun {}>", name),
+				None => write!(f, "<fun>"),
+			},
+			Self::Native(native) => write!(f, "<native fun {}>", native.name),
+			Self::List(list) => {
+				write!(f, "[")?;
+				for (i, item) in list.borrow().iter().enumerate() {
+					if i > 0 {
+						write!(f, ", ")?;
+					}
+					write!(f, "{}", item)?;
+				}
+				write!(f, "]")
+			}
+			Self::Map(map) => {
+				write!(f, "{{")?;
+				for (i, (key, value)) in map.borrow().iter().enumerate() {
+					if i > 0 {
+						write!(f, ",")?;
+					}
+					write!(f, " {}: {}", key, value)?;
+				}
+				write!(f, " }}")
+			}
+			Self::Range(start, end) => write!(f, "{}..{}", start, end),
+		}
+	}
+}
+
+impl ops::Add for NodeResult {
+	type Output = OpResult;
+
+	fn add(self, rhs: Self) -> OpResult {
+		match (self.numbers(&rhs), &self, &rhs) {
+			(Some(Numbers::Int(lhs, rhs)), ..) => {
+				Self::checked("+", lhs, rhs, lhs.checked_add(rhs))
+			}
+			(Some(Numbers::Float(lhs, rhs)), ..) => Ok(Self::Float(lhs + rhs)),
+			(_, Self::String(lhs), Self::String(rhs)) => {
+				Ok(Self::String(format!("{}{}", lhs, rhs)))
+			}
+			_ => Err(Self::mismatch("+", &self, &rhs)),
+		}
+	}
+}
+
+impl ops::Sub for NodeResult {
+	type Output = OpResult;
+
+	fn sub(self, rhs: Self) -> OpResult {
+		match self.numbers(&rhs) {
+			Some(Numbers::Int(lhs, rhs)) => {
+				Self::checked("-", lhs, rhs, lhs.checked_sub(rhs))
+			}
+			Some(Numbers::Float(lhs, rhs)) => Ok(Self::Float(lhs - rhs)),
+			None => Err(Self::mismatch("-", &self, &rhs)),
+		}
+	}
+}
+
+impl ops::Mul for NodeResult {
+	type Output = OpResult;
+
+	fn mul(self, rhs: Self) -> OpResult {
+		match self.numbers(&rhs) {
+			Some(Numbers::Int(lhs, rhs)) => {
+				Self::checked("*", lhs, rhs, lhs.checked_mul(rhs))
+			}
+			Some(Numbers::Float(lhs, rhs)) => Ok(Self::Float(lhs * rhs)),
+			None => Err(Self::mismatch("*", &self, &rhs)),
+		}
+	}
+}
+
+impl ops::Div for NodeResult {
+	type Output = OpResult;
+
+	// ints divide rounding down, so `-7 / 2` is `-4`, while floats divide
+	// exactly
+	fn div(self, rhs: Self) -> OpResult {
+		match self.numbers(&rhs) {
+			Some(Numbers::Int(_, 0)) => Err("division by zero".into()),
+			Some(Numbers::Int(lhs, rhs)) => {
+				Self::checked("/", lhs, rhs, Self::floor_div(lhs, rhs))
+			}
+			Some(Numbers::Float(_, 0.0)) => Err("division by zero".into()),
+			Some(Numbers::Float(lhs, rhs)) => Ok(Self::Float(lhs / rhs)),
+			None => Err(Self::mismatch("/", &self, &rhs)),
+		}
+	}
+}
+
+impl ops::Rem for NodeResult {
+	type Output = OpResult;
+
+	// the remainder of rounding-down division, which takes the sign of the
+	// divisor, so `-7 % 2` is `1` and `a == a / b * b + a % b` for ints
+	fn rem(self, rhs: Self) -> OpResult {
+		match self.numbers(&rhs) {
+			Some(Numbers::Int(_, 0)) => Err("division by zero".into()),
+			Some(Numbers::Int(lhs, rhs)) => {
+				Ok(Self::Int(Self::floor_rem(lhs, rhs)))
+			}
+			Some(Numbers::Float(_, 0.0)) => Err("division by zero".into()),
+			Some(Numbers::Float(lhs, rhs)) => {
+				let remainder = lhs % rhs;
+				Ok(Self::Float(
+					if remainder != 0.0 && (remainder < 0.0) != (rhs < 0.0) {
+						remainder + rhs
+					} else {
+						remainder
+					},
+				))
+			}
+			None => Err(Self::mismatch("%", &self, &rhs)),
+		}
+	}
+}
+
+impl ops::BitAnd for NodeResult {
+	type Output = OpResult;
+
+	fn bitand(self, rhs: Self) -> OpResult {
+		let (lhs, rhs) = Self::bits("&", &self, &rhs)?;
+		Ok(Self::Int(lhs & rhs))
+	}
+}
+
+impl ops::BitOr for NodeResult {
+	type Output = OpResult;
+
+	fn bitor(self, rhs: Self) -> OpResult {
+		let (lhs, rhs) = Self::bits("|", &self, &rhs)?;
+		Ok(Self::Int(lhs | rhs))
+	}
+}
+
+impl ops::BitXor for NodeResult {
+	type Output = OpResult;
+
+	fn bitxor(self, rhs: Self) -> OpResult {
+		let (lhs, rhs) = Self::bits("^", &self, &rhs)?;
+		Ok(Self::Int(lhs ^ rhs))
+	}
+}
+
+impl ops::Neg for NodeResult {
+	type Output = OpResult;
+
+	fn neg(self) -> OpResult {
+		match self {
+			Self::Int(num) => {
+				num.checked_neg().map(Self::Int).ok_or_else(|| {
+					format!(
+						"integer overflow: -({}) does not fit in an int",
+						num
+					)
+				})
+			}
+			Self::Float(num) => Ok(Self::Float(-num)),
+			_ => Err(format!("cannot negate {}", self.type_name())),
+		}
+	}
+}
+
+impl ops::Not for NodeResult {
+	type Output = OpResult;
+
+	// `!` on booleans and `~` on ints
+	fn not(self) -> OpResult {
+		match self {
+			Self::Boolean(bool) => Ok(Self::Boolean(!bool)),
+			Self::Int(num) => Ok(Self::Int(!num)),
+			_ => Err(format!("cannot negate {}", self.type_name())),
+		}
+	}
+}
+
+impl RuntimeError {
+	pub(crate) fn new(message: impl Into<String>, span: Span) -> Self {
+		Self {
+			source: None,
+			related: Vec::new(),
+			message: message.into(),
+			span,
+		}
+	}
+}
+
+impl From<RuntimeError> for Unwind {
+	fn from(err: RuntimeError) -> Self { Self::Error(err) }
+}
+
+impl fmt::Display for RuntimeError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{} at {}:{}",
+			self.message, self.span.line, self.span.col
+		)
+	}
+}
+
+impl Unwind {
+	// errors that don't know their file yet happened in `source`
+	fn within(self, source: &Rc<Source>) -> Self {
+		match self {
+			Self::Error(mut err) if err.source.is_none() => {
+				err.source = Some(Rc::clone(source));
+				Self::Error(err)
+			}
+			unwind => unwind,
+		}
+	}
+}
+
+pub struct Interpreter {
+	globals: Env,
+	env: Env,
+	depth: usize,
+	// where the stack was when `run` was called, to measure how much of it
+	// the calls since have used
+	stack_base: usize,
+	// the file the code being run comes from
+	source: Rc<Source>,
+	// the globals of every module run so far, by canonical path
+	modules: HashMap<PathBuf, Env>,
+	// the modules being run right now, outermost first, with their names
+	loading: Vec<(PathBuf, String)>,
+	builtins: HashMap<String, NodeResult>,
+	// whether warnings in an imported module stop it from loading
+	strict: bool,
+	// the warnings found in imported modules, for the caller to show
+	warnings: Vec<(Rc<Source>, Diagnostic)>,
+}
+
+impl Interpreter {
+	pub fn new() -> Interpreter {
+		let globals = Environment::global();
+
+		Self {
+			env: Rc::clone(&globals),
+			globals,
+			depth: 0,
+			stack_base: stack_address(),
+			source: Source::new("<input>", "", ""),
+			modules: HashMap::new(),
+			loading: Vec::new(),
+			builtins: HashMap::new(),
+			strict: false,
+			warnings: Vec::new(),
+		}
+	}
+
+	// Sets the file the next call to `run` runs code from. A file on disk
+	// counts as being loaded while it runs, so importing it again, directly
+	// or through other modules, is reported as a cycle rather than running
+	// it a second time.
+	pub fn set_source(&mut self, source: Rc<Source>) {
+		self.loading = fs::canonicalize(&source.path)
+			.map(|key| vec![(key, source.name.clone())])
+			.unwrap_or_default();
+		self.source = source;
+	}
+
+	pub fn set_strict(&mut self, strict: bool) { self.strict = strict; }
+
+	// the warnings of the modules imported since this was last called
+	pub fn take_warnings(&mut self) -> Vec<(Rc<Source>, Diagnostic)> {
+		mem::take(&mut self.warnings)
+	}
+
+	// names bound at the top level, which later input may refer to
+	pub fn global_names(&self) -> Vec<String> {
+		self.globals.borrow().names().cloned().collect()
+	}
+
+	pub fn run(&mut self, stmts: &[Node]) -> Result<NodeResult, RuntimeError> {
+		self.stack_base = stack_address();
+		match self
+			.exec(stmts)
+			.map_err(|unwind| unwind.within(&self.source))
+		{
+			Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+			Err(Unwind::Error(err)) => Err(err),
+			// the resolver rejects `break` and `continue` outside of loops
+			Err(Unwind::Break(_)) | Err(Unwind::Continue(_)) => {
+				Ok(NodeResult::Nil)
+			}
+		}
+	}
+
+	fn exec(&mut self, stmts: &[Node]) -> EvalResult {
+		let mut result = NodeResult::Nil;
+		for stmt in stmts {
+			result = self.eval(stmt)?;
+		}
+
+		Ok(result)
+	}
+
+	fn run_in(&mut self, stmts: &[Node], env: Env) -> EvalResult {
+		let previous = mem::replace(&mut self.env, env);
+		let result = self.exec(stmts);
+		self.env = previous;
+
+		result
+	}
+
+	fn eval(&mut self, node: &Node) -> EvalResult {
+		match &node.kind {
+			NodeKind::List(items) => {
+				let items = items
+					.iter()
+					.map(|item| self.eval(item))
+					.collect::<Result<Vec<_>, _>>()?;
+
+				Ok(NodeResult::List(Rc::new(RefCell::new(items))))
+			}
+			NodeKind::Map(entries) => {
+				let mut map = BTreeMap::new();
+				for (key, value) in entries {
+					map.insert(key.clone(), self.eval(value)?);
+				}
+
+				Ok(NodeResult::Map(Rc::new(RefCell::new(map))))
+			}
+			NodeKind::IntLiteral(num) => Ok(NodeResult::Int(*num)),
+			NodeKind::FloatLiteral(num) => Ok(NodeResult::Float(*num)),
+			NodeKind::BooleanLiteral(bool) => Ok(NodeResult::Boolean(*bool)),
+			NodeKind::StringLiteral(str) => Ok(NodeResult::String(str.clone())),
+			NodeKind::Interpolation(parts) => {
+				let mut str = String::new();
+				for part in parts {
+					str += &self.eval(part)?.to_string();
+				}
+
+				Ok(NodeResult::String(str))
+			}
+			NodeKind::Ident(name) => {
+				self.env.borrow().get(name).ok_or_else(|| {
+					RuntimeError::new(
+						format!("undefined variable `{}`", name),
+						node.span,
+					)
+					.into()
+				})
+			}
+			NodeKind::UnaryExpr { op, rhs } => {
+				let rhs = self.eval(rhs)?;
+				let result = match op.kind {
+					TokenKind::Minus => -rhs,
+					TokenKind::Not
+						if !matches!(rhs, NodeResult::Boolean(_)) =>
+					{
+						Err(format!("cannot apply `!` to {}", rhs.type_name()))
+					}
+					TokenKind::BitNot if !matches!(rhs, NodeResult::Int(_)) => {
+						Err(format!("cannot apply `~` to {}", rhs.type_name()))
+					}
+					_ => !rhs,
+				};
+
+				result.map_err(|message| {
+					RuntimeError::new(message, op.span).into()
+				})
+			}
+			NodeKind::BinExpr { op, lhs, rhs } => self.binary(op, lhs, rhs),
+			NodeKind::Member { object, field } => match self.eval(object)? {
+				NodeResult::Map(map) => Ok(map
+					.borrow()
+					.get(field)
+					.cloned()
+					.unwrap_or(NodeResult::Nil)),
+				value => Err(RuntimeError::new(
+					format!(
+						"cannot access `{}` on {}",
+						field,
+						value.type_name()
+					),
+					node.span,
+				)
+				.into()),
+			},
+			NodeKind::SetMember {
+				object,
+				field,
+				value,
+			} => match self.eval(object)? {
+				NodeResult::Map(map) => {
+					let value = self.eval(value)?;
+					map.borrow_mut().insert(field.clone(), value.clone());
+
+					Ok(value)
+				}
+				target => Err(RuntimeError::new(
+					format!(
+						"cannot assign to `{}` on {}",
+						field,
+						target.type_name()
+					),
+					object.span,
+				)
+				.into()),
+			},
+			NodeKind::Index { object, index } => {
+				let target = self.eval(object)?;
+				if let NodeResult::Map(map) = &target {
+					let key = self.key(index)?;
+					return Ok(map
+						.borrow()
+						.get(&key)
+						.cloned()
+						.unwrap_or(NodeResult::Nil));
+				}
+
+				let len = target.length().ok_or_else(|| {
+					RuntimeError::new(
+						format!("cannot index into {}", target.type_name()),
+						object.span,
+					)
+				})?;
+				let i = self.eval(index)?.element(len).map_err(|message| {
+					RuntimeError::new(message, index.span)
+				})?;
+
+				Ok(match target {
+					NodeResult::List(list) => list.borrow()[i].clone(),
+					NodeResult::String(str) => {
+						NodeResult::String(str.chars().nth(i).unwrap().into())
+					}
+					_ => unreachable!(),
+				})
+			}
+			NodeKind::Slice { object, start, end } => {
+				let target = self.eval(object)?;
+				let len = target.length().ok_or_else(|| {
+					RuntimeError::new(
+						format!("cannot slice {}", target.type_name()),
+						object.span,
+					)
+				})?;
+
+				let mut bounds = [0, len];
+				for (bound, expr) in bounds.iter_mut().zip([start, end]) {
+					if let Some(expr) = expr {
+						*bound = self.eval(expr)?.boundary(len).map_err(
+							|message| RuntimeError::new(message, expr.span),
+						)?;
+					}
+				}
+				let [start, end] = bounds;
+				if start > end {
+					return Err(RuntimeError::new(
+						format!(
+							"slice starts at {} but ends at {}",
+							start, end
+						),
+						node.span,
+					)
+					.into());
+				}
+
+				Ok(match target {
+					NodeResult::List(list) => NodeResult::List(Rc::new(
+						RefCell::new(list.borrow()[start..end].to_vec()),
+					)),
+					NodeResult::String(str) => NodeResult::String(
+						str.chars().skip(start).take(end - start).collect(),
+					),
+					_ => unreachable!(),
+				})
+			}
+			NodeKind::SetIndex {
+				object,
+				index,
+				value,
+			} => {
+				let target = self.eval(object)?;
+				if let NodeResult::Map(map) = &target {
+					let key = self.key(index)?;
+					let value = self.eval(value)?;
+					map.borrow_mut().insert(key, value.clone());
+
+					return Ok(value);
+				}
+
+				let position = self.eval(index)?;
+				let value = self.eval(value)?;
+
+				match &target {
+					NodeResult::List(list) => {
+						let mut list = list.borrow_mut();
+						let i = position.element(list.len()).map_err(
+							|message| RuntimeError::new(message, index.span),
+						)?;
+						list[i] = value.clone();
+
+						Ok(value)
+					}
+					_ => Err(RuntimeError::new(
+						format!("cannot assign into {}", target.type_name()),
+						object.span,
+					)
+					.into()),
+				}
+			}
+			NodeKind::Let { name, value, .. } => {
+				let value = self.eval(value)?;
+				self.env.borrow_mut().define(name.clone(), value);
+
+				Ok(NodeResult::Nil)
+			}
+			NodeKind::Assign { name, value } => {
+				let value = self.eval(value)?;
+				if self.env.borrow_mut().assign(name, value.clone()) {
+					Ok(value)
+				} else {
+					Err(RuntimeError::new(
+						format!("undefined variable `{}`", name),
+						node.span,
+					)
+					.into())
+				}
+			}
+			NodeKind::Fun {
+				name, params, body, ..
+			} => {
+				let fun = NodeResult::Function(Rc::new(Function {
+					name: name.clone(),
+					params: params.clone(),
+					body: *body.clone(),
+					closure: Rc::clone(&self.env),
+					source: Rc::clone(&self.source),
+				}));
+
+				match name {
+					Some(name) => {
+						self.env.borrow_mut().define(name.clone(), fun);
+						Ok(NodeResult::Nil)
+					}
+					None => Ok(fun),
+				}
+			}
+			NodeKind::If {
+				cond,
+				body,
+				else_body,
+			} => {
+				if self.condition(cond, "condition")? {
+					self.eval(body)
+				} else if let Some(else_body) = else_body {
+					self.eval(else_body)
+				} else {
+					Ok(NodeResult::Nil)
+				}
+			}
+			NodeKind::Match { subject, arms } => {
+				let value = self.eval(subject)?;
+				for arm in arms {
+					if let Some(result) = self.arm(arm, &value)? {
+						return Ok(result);
+					}
+				}
+
+				Err(RuntimeError::new(
+					format!("non-exhaustive match: no arm matches `{}`", value),
+					subject.span,
+				)
+				.into())
+			}
+			NodeKind::Block(stmts) => {
+				let env = Environment::child(&self.env);
+				self.run_in(stmts, env)
+			}
+			NodeKind::Call { callee, args } => {
+				let callee = self.eval(callee)?;
+				let args = args
+					.iter()
+					.map(|arg| self.eval(arg))
+					.collect::<Result<Vec<_>, _>>()?;
+
+				self.call(callee, args, node.span)
+			}
+			NodeKind::Return(value) => {
+				let value = match value {
+					Some(value) => self.eval(value)?,
+					None => NodeResult::Nil,
+				};
+
+				Err(Unwind::Return(value))
+			}
+			NodeKind::While { label, cond, body } => {
+				while self.condition(cond, "condition")? {
+					let result = self.eval(body);
+					if !Self::looping(result, label)? {
+						break;
+					}
+				}
+
+				Ok(NodeResult::Nil)
+			}
+			NodeKind::For {
+				label,
+				name,
+				iterable,
+				body,
+			} => {
+				let values =
+					self.eval(iterable)?.iterate().map_err(|message| {
+						RuntimeError::new(message, iterable.span)
+					})?;
+
+				// every iteration gets its own binding, so closures created in
+				// the body keep the value they saw
+				for value in values {
+					let env = Environment::child(&self.env);
+					env.borrow_mut().define(name.clone(), value);
+					let result = self.run_in(std::slice::from_ref(body), env);
+					if !Self::looping(result, label)? {
+						break;
+					}
+				}
+
+				Ok(NodeResult::Nil)
+			}
+			NodeKind::Break(label) => Err(Unwind::Break(label.clone())),
+			NodeKind::Continue(label) => Err(Unwind::Continue(label.clone())),
+			NodeKind::Import { names, path: None } => {
+				for name in names {
+					let module = self.builtin(name).ok_or_else(|| {
+						RuntimeError::new(
+							format!("no built-in module named `{}`", name),
+							node.span,
+						)
+					})?;
+					self.env.borrow_mut().define(name.clone(), module);
+				}
+
+				Ok(NodeResult::Nil)
+			}
+			NodeKind::Import {
+				names,
+				path: Some(path),
+			} => {
+				let exports = self.import(path, node.span)?;
+				for name in names {
+					let value =
+						exports.borrow().get(name).ok_or_else(|| {
+							RuntimeError::new(
+								format!("module `{}` has no `{}`", path, name),
+								node.span,
+							)
+						})?;
+					self.env.borrow_mut().define(name.clone(), value);
+				}
+
+				Ok(NodeResult::Nil)
+			}
+		}
+	}
+
+	// runs the arm if one of its patterns matches and its guard holds, with
+	// the value bound to the arm's binding, if any
+	fn arm(
+		&mut self,
+		arm: &MatchArm,
+		value: &NodeResult,
+	) -> Result<Option<NodeResult>, Unwind> {
+		let pattern = match arm.patterns.iter().find(|p| value.matches(&p.kind))
+		{
+			Some(pattern) => pattern,
+			None => return Ok(None),
+		};
+
+		let env = Environment::child(&self.env);
+		if let PatternKind::Binding(name) = &pattern.kind {
+			env.borrow_mut().define(name.clone(), value.clone());
+		}
+		let previous = mem::replace(&mut self.env, env);
+		let result = self.guarded(arm);
+		self.env = previous;
+
+		result
+	}
+
+	fn guarded(
+		&mut self,
+		arm: &MatchArm,
+	) -> Result<Option<NodeResult>, Unwind> {
+		if let Some(guard) = &arm.guard {
+			if !self.condition(guard, "guard")? {
+				return Ok(None);
+			}
+		}
+
+		self.eval(&arm.body).map(Some)
+	}
+
+	// maps are indexed by strings only
+	fn key(&mut self, node: &Node) -> Result<String, Unwind> {
+		match self.eval(node)? {
+			NodeResult::String(key) => Ok(key),
+			value => Err(RuntimeError::new(
+				format!(
+					"map key must be a string, found {}",
+					value.type_name()
+				),
+				node.span,
+			)
+			.into()),
+		}
+	}
+
+	// There is no implicit truthiness: conditions, guards and the operands of
+	// `&&`, `||` and `!` must be booleans, and anything else, `nil` and `0`
+	// included, is a type error.
+	fn condition(&mut self, node: &Node, what: &str) -> Result<bool, Unwind> {
+		match self.eval(node)? {
+			NodeResult::Boolean(bool) => Ok(bool),
+			value => Err(RuntimeError::new(
+				format!(
+					"expected boolean {}, found {}",
+					what,
+					value.type_name()
+				),
+				node.span,
+			)
+			.into()),
+		}
+	}
+
+	// whether a loop labeled `label` goes on after its body finished with
+	// `result`; jumps aimed at an outer loop are passed on
+	fn looping(
+		result: EvalResult,
+		label: &Option<String>,
+	) -> Result<bool, Unwind> {
+		match result {
+			Ok(_) => Ok(true),
+			Err(Unwind::Break(target))
+				if target.is_none() || &target == label =>
+			{
+				Ok(false)
+			}
+			Err(Unwind::Continue(target))
+				if target.is_none() || &target == label =>
+			{
+				Ok(true)
+			}
+			Err(unwind) => Err(unwind),
+		}
+	}
+
+	// errors about the call itself point at the whole call expression
+	fn call(
+		&mut self,
+		callee: NodeResult,
+		args: Vec<NodeResult>,
+		span: Span,
+	) -> EvalResult {
+		let (name, arity) = match &callee {
+			NodeResult::Function(fun) => (fun.name.clone(), fun.params.len()),
+			NodeResult::Native(native) => {
+				(Some(native.name.to_string()), native.arity)
+			}
+			value => {
+				return Err(RuntimeError::new(
+					format!("cannot call {}", value.type_name()),
+					span,
+				)
+				.into())
+			}
+		};
+
+		if args.len() != arity {
+			let plural = |count| if count == 1 { "" } else { "s" };
+			return Err(RuntimeError::new(
+				format!(
+					"{} takes {} argument{} but {} {} supplied",
+					name.map_or("function".into(), |name| format!(
+						"`{}`",
+						name
+					)),
+					arity,
+					plural(arity),
+					args.len(),
+					if args.len() == 1 { "was" } else { "were" }
+				),
+				span,
+			)
+			.into());
+		}
+
+		let fun = match callee {
+			NodeResult::Function(fun) => fun,
+			NodeResult::Native(native) => {
+				return (native.fun)(&args)
+					.map_err(|message| RuntimeError::new(message, span).into())
+			}
+			_ => unreachable!(),
+		};
+		let stack_used = self.stack_base.abs_diff(stack_address());
+		if self.depth == MAX_CALL_DEPTH
+			|| stack_used > STACK_SIZE - STACK_RESERVE
+		{
+			return Err(RuntimeError::new(
+				format!(
+					"stack overflow: calls nested more than {} deep",
+					self.depth
+				),
+				span,
+			)
+			.into());
+		}
+
+		let env = Environment::child(&fun.closure);
+		for (param, arg) in fun.params.iter().zip(args) {
+			env.borrow_mut().define(param.clone(), arg);
+		}
+
+		self.depth += 1;
+		let previous = mem::replace(&mut self.source, Rc::clone(&fun.source));
+		let result = self.run_in(std::slice::from_ref(&fun.body), env);
+		self.source = previous;
+		self.depth -= 1;
+
+		match result {
+			Err(Unwind::Return(value)) => Ok(value),
+			result => result.map_err(|unwind| unwind.within(&fun.source)),
+		}
+	}
+
+	// built-in modules are created once, so every import shares them
+	fn builtin(&mut self, name: &str) -> Option<NodeResult> {
+		if !self.builtins.contains_key(name) {
+			self.builtins.insert(name.into(), stdlib::builtin(name)?);
+		}
+
+		self.builtins.get(name).cloned()
+	}
+
+	// runs the module at `path`, relative to the current file, the first time
+	// it is imported, and returns its globals
+	fn import(&mut self, path: &str, span: Span) -> Result<Env, Unwind> {
+		let file = self.source.dir().join(path);
+		// named the way the importing file was, so they read alike in errors
+		let name = Path::new(&self.source.name)
+			.parent()
+			.map_or_else(|| path.into(), |dir| dir.join(path))
+			.display()
+			.to_string();
+		let error = |message: String| RuntimeError::new(message, span);
+
+		let key = fs::canonicalize(&file).map_err(|err| {
+			error(format!("cannot load module `{}`: {}", name, err))
+		})?;
+		if let Some(env) = self.modules.get(&key) {
+			return Ok(Rc::clone(env));
+		}
+		if let Some(start) =
+			self.loading.iter().position(|(loading, _)| *loading == key)
+		{
+			let cycle = self.loading[start..]
+				.iter()
+				.map(|(_, name)| name)
+				.chain(iter::once(&name))
+				.map(|name| format!("`{}`", name))
+				.collect::<Vec<_>>();
+			return Err(
+				error(format!("import cycle: {}", cycle.join(" -> "))).into()
+			);
+		}
+
+		let text = fs::read_to_string(&key).map_err(|err| {
+			error(format!("cannot load module `{}`: {}", name, err))
+		})?;
+		let source = Source::new(name.clone(), file, text);
+		let related = |message: String, diagnostics: Vec<Diagnostic>| {
+			let mut err = error(message);
+			err.related = diagnostics
+				.into_iter()
+				.map(|diagnostic| (Rc::clone(&source), diagnostic))
+				.collect();
+			err
+		};
+		let (tree, warnings) =
+			module::parse(&source).map_err(|diagnostics| {
+				related(
+					format!("could not load module `{}`", name),
+					diagnostics,
+				)
+			})?;
+		if self.strict && !warnings.is_empty() {
+			return Err(related(
+				format!(
+					"not loading module `{}` due to warnings in strict mode",
+					name
+				),
+				warnings,
+			)
+			.into());
+		}
+		self.warnings.extend(
+			warnings
+				.into_iter()
+				.map(|diagnostic| (Rc::clone(&source), diagnostic)),
+		);
+
+		let env = Environment::global();
+		self.loading.push((key.clone(), name));
+		let previous_env = mem::replace(&mut self.env, Rc::clone(&env));
+		let previous_source =
+			mem::replace(&mut self.source, Rc::clone(&source));
+		let result = self.exec(&tree);
+		self.source = previous_source;
+		self.env = previous_env;
+		self.loading.pop();
+
+		result.map_err(|unwind| unwind.within(&source))?;
+		self.modules.insert(key, Rc::clone(&env));
+
+		Ok(env)
+	}
+
+	fn binary(&mut self, op: &Token, lhs: &Node, rhs: &Node) -> EvalResult {
+		// logical operators short-circuit, so the right hand side is only
+		// evaluated when it decides the result
+		if matches!(op.kind, TokenKind::And | TokenKind::Or) {
+			let result = match (&op.kind, self.condition(lhs, "operand")?) {
+				(TokenKind::And, false) => false,
+				(TokenKind::Or, true) => true,
+				_ => self.condition(rhs, "operand")?,
+			};
+
+			return Ok(NodeResult::Boolean(result));
+		}
+
+		let lhs = self.eval(lhs)?;
+		let rhs = self.eval(rhs)?;
+		let result = match op.kind {
+			TokenKind::Plus => lhs + rhs,
+			TokenKind::Minus => lhs - rhs,
+			TokenKind::Multiply => lhs * rhs,
+			TokenKind::Divide => lhs / rhs,
+			TokenKind::Mod => lhs % rhs,
+			TokenKind::BitAnd => lhs & rhs,
+			TokenKind::BitOr => lhs | rhs,
+			TokenKind::Xor => lhs ^ rhs,
+			TokenKind::Range => lhs.range(rhs),
+			TokenKind::Eq => Ok(NodeResult::Boolean(lhs == rhs)),
+			TokenKind::NotEq => Ok(NodeResult::Boolean(lhs != rhs)),
+			TokenKind::Less => lhs
+				.compare(&rhs, "<")
+				.map(|ord| NodeResult::Boolean(ord.is_lt())),
+			TokenKind::LessEq => lhs
+				.compare(&rhs, "<=")
+				.map(|ord| NodeResult::Boolean(ord.is_le())),
+			TokenKind::Greater => lhs
+				.compare(&rhs, ">")
+				.map(|ord| NodeResult::Boolean(ord.is_gt())),
+			TokenKind::GreaterEq => lhs
+				.compare(&rhs, ">=")
+				.map(|ord| NodeResult::Boolean(ord.is_ge())),
+			_ => unreachable!("{:?} is not a binary operator", op.kind),
+		};
+
+		result.map_err(|message| RuntimeError::new(message, op.span).into())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	use std::thread;
+
+	use crate::{lexer::Lexer, parser::Parser};
+
+	fn run(input: &str) -> Result<NodeResult, RuntimeError> {
+		let tree = Parser::new(Lexer::new(input)).parse().unwrap();
+		// every error comes from the same input, so the source isn't compared
+		Interpreter::new().run(&tree).map_err(|err| RuntimeError {
+			source: None,
+			..err
+		})
+	}
+
+	#[test]
+	fn eval_arithmetic() {
+		assert_eq!(run("1 + 2 * 3 - 4 / 2"), Ok(NodeResult::Int(5)));
+		assert_eq!(run("-(7 % 4)"), Ok(NodeResult::Int(-3)));
+	}
+
+	#[test]
+	fn eval_numbers() {
+		assert_eq!(run("1 + 0.5"), Ok(NodeResult::Float(1.5)));
+		assert_eq!(run("7 / 2.0"), Ok(NodeResult::Float(3.5)));
+		assert_eq!(
+			run("[7 / 2, -7 / 2, 7 % -2, -7 % 2, -7.5 % 2]")
+				.map(|list| list.to_string()),
+			Ok("[3, -4, -1, 1, 0.5]".into())
+		);
+		assert_eq!(
+			run("1 == 1.0 && 2 > 1.5 && match 2.0 { 2 -> true, _ -> false }"),
+			Ok(NodeResult::Boolean(true))
+		);
+		assert_eq!(run("4.0").map(|num| num.to_string()), Ok("4.0".into()));
+		assert_eq!(
+			run("9223372036854775807 + 1"),
+			Err(RuntimeError::new(
+				"integer overflow: 9223372036854775807 + 1 does not fit in an \
+				 int",
+				Span::new(20, 21, 1, 20)
+			))
+		);
+		assert_eq!(
+			run("1 / 0"),
+			Err(RuntimeError::new("division by zero", Span::new(2, 3, 1, 2)))
+		);
+	}
+
+	#[test]
+	fn eval_bitwise() {
+		assert_eq!(run("3 ^ 6"), Ok(NodeResult::Int(5)));
+		assert_eq!(run("(12 & 10) | ~0"), Ok(NodeResult::Int(-1)));
+		assert_eq!(
+			run("3 ^ 6.0"),
+			Err(RuntimeError::new(
+				"cannot apply `^` to int and float",
+				Span::new(2, 3, 1, 2)
+			))
+		);
+		assert_eq!(
+			run("~1.5"),
+			Err(RuntimeError::new(
+				"cannot apply `~` to float",
+				Span::new(0, 1, 1, 0)
+			))
+		);
+	}
+
+	#[test]
+	fn eval_logic() {
+		assert_eq!(
+			run("!(1 < 2) || \"a\" + \"b\" == \"ab\""),
+			Ok(NodeResult::Boolean(true))
+		);
+		assert_eq!(run("false && undefined"), Ok(NodeResult::Boolean(false)));
+	}
+
+	#[test]
+	fn eval_variables() {
+		assert_eq!(
+			run("let a = 2\nlet b = a * 3\na = b + 1\na"),
+			Ok(NodeResult::Int(7))
+		);
+	}
+
+	#[test]
+	fn eval_type_error() {
+		assert_eq!(
+			run("1 + true"),
+			Err(RuntimeError::new(
+				"cannot apply `+` to int and boolean",
+				Span::new(2, 3, 1, 2)
+			))
+		);
+	}
+
+	#[test]
+	fn eval_block_scopes() {
+		assert_eq!(
+			run("let a = 1\nlet b = 0\n{\n\tlet a = 2\n\tb = a\n}\na + b"),
+			Ok(NodeResult::Int(3))
+		);
+	}
+
+	#[test]
+	fn eval_if() {
+		let sign = "fun sign(n) {\n\tif n < 0 { -1 } else if n == 0 { 0 } \
+		            else {\n\t\tlet one = 1\n\t\tone\n\t}\n}\n";
+
+		for (input, output) in [("-3", -1), ("0", 0), ("8", 1)] {
+			assert_eq!(
+				run(&format!("{}sign({})", sign, input)),
+				Ok(NodeResult::Int(output))
+			);
+		}
+		assert_eq!(run("let x = if false { 1 }\nx"), Ok(NodeResult::Nil));
+	}
+
+	#[test]
+	fn eval_conditions() {
+		assert_eq!(
+			run("if 0 { 1 } else { 2 }"),
+			Err(RuntimeError::new(
+				"expected boolean condition, found int",
+				Span::new(3, 4, 1, 3)
+			))
+		);
+		assert_eq!(
+			run("true && \"yes\""),
+			Err(RuntimeError::new(
+				"expected boolean operand, found string",
+				Span::new(8, 13, 1, 8)
+			))
+		);
+	}
+
+	#[test]
+	fn eval_loops() {
+		assert_eq!(
+			run(
+				"let i = 0\nlet sum = 0\nwhile i < 5 {\n\ti = i + 1\n\tif i \
+				 == 2 { continue }\n\tsum = sum + i\n}\nsum"
+			),
+			Ok(NodeResult::Int(13))
+		);
+		assert_eq!(
+			run("let out = \"\"\nfor ch in \"abc\" { out = ch + out }\nout"),
+			Ok(NodeResult::String("cba".into()))
+		);
+		assert_eq!(run("let r = 1..4\nr"), Ok(NodeResult::Range(1, 4)));
+	}
+
+	#[test]
+	fn eval_labeled_loops() {
+		assert_eq!(
+			run("let pairs = 0\n'outer: for i in 0..10 {\n\tfor j in 0..10 \
+			     {\n\t\tif j > i { continue 'outer }\n\t\tif i == 4 { break \
+			     'outer }\n\t\tpairs = pairs + 1\n\t}\n}\npairs"),
+			Ok(NodeResult::Int(10))
+		);
+		assert_eq!(
+			run("for i in 0.5..2 {}"),
+			Err(RuntimeError::new(
+				"cannot apply `..` to float and int",
+				Span::new(12, 14, 1, 12)
+			))
+		);
+	}
+
+	#[test]
+	fn eval_lists() {
+		assert_eq!(
+			run("let xs = [1, 2, 3, 4]\nxs[0] + xs[-1]"),
+			Ok(NodeResult::Int(5))
+		);
+		assert_eq!(
+			run("let xs = [1, 2, 3, 4]\n[xs[1:3], xs[:-2], xs[2:], \
+			     \"hello\"[1:4]]")
+			.map(|list| list.to_string()),
+			Ok("[[2, 3], [1, 2], [3, 4], ell]".into())
+		);
+		// lists are shared, not copied, when assigned or passed around
+		assert_eq!(
+			run("let xs = [1, [2]]\nlet ys = xs\nfun set(l) { l[-1][0] = 5 \
+			     }\nset(ys)\nys[0] = 0\nxs")
+			.map(|list| list.to_string()),
+			Ok("[0, [5]]".into())
+		);
+		assert_eq!(
+			run("let sum = 0\nfor x in [1, 2, 3] { sum = sum + x }\nsum"),
+			Ok(NodeResult::Int(6))
+		);
+	}
+
+	#[test]
+	fn eval_index_errors() {
+		assert_eq!(
+			run("let xs = [1, 2]\nxs[1 + 1]"),
+			Err(RuntimeError::new(
+				"index 2 out of range for length 2",
+				Span::new(19, 24, 2, 3)
+			))
+		);
+		assert_eq!(
+			run("[1, 2][-3] = 0").map_err(|err| err.message),
+			Err("index -3 out of range for length 2".into())
+		);
+		assert_eq!(
+			run("\"abc\"[1:9]").map_err(|err| err.message),
+			Err("slice bound 9 out of range for length 3".into())
+		);
+		assert_eq!(
+			run("true[0]").map_err(|err| err.message),
+			Err("cannot index into boolean".into())
+		);
+	}
+
+	#[test]
+	fn eval_maps() {
+		assert_eq!(
+			run("let m = { name: \"x\", \"n\": 1 }\nm.n = m[\"n\"] + \
+			     1\nm[\"k\" + \"ey\"] = m.name\nm")
+			.map(|map| map.to_string()),
+			Ok("{ key: x, n: 2, name: x }".into())
+		);
+		assert_eq!(run("{ a: 1 }.b"), Ok(NodeResult::Nil));
+		assert_eq!(
+			run(
+				"let keys = \"\"\nfor k in { b: 1, a: 2 } { keys = keys + k \
+				 }\nkeys"
+			),
+			Ok(NodeResult::String("ab".into()))
+		);
+		assert_eq!(
+			run("let m = {}\nm[1] = 2"),
+			Err(RuntimeError::new(
+				"map key must be a string, found int",
+				Span::new(13, 14, 2, 2)
+			))
+		);
+	}
+
+	#[test]
+	fn eval_interpolation() {
+		assert_eq!(
+			run(
+				"let n = 2\nlet m = { a: { b: [1] } }\n\"${n} + ${n} = ${n + \
+				 n}, ${ m.a } ${ \"in${ \"ner\" }\" } \\${n}\""
+			),
+			Ok(NodeResult::String(
+				"2 + 2 = 4, { b: [1] } inner ${n}".into()
+			))
+		);
+	}
+
+	#[test]
+	fn eval_match() {
+		let classify = "fun classify(n) {\n\tmatch n {\n\t\t0 -> \
+		                \"zero\"\n\t\t1 | 2 | 3 -> \"small\"\n\t\tn if n < 0 \
+		                -> \"negative\"\n\t\t_ -> \"large\"\n\t}\n}\n";
+
+		for (input, output) in [
+			("0", "zero"),
+			("2", "small"),
+			("-4", "negative"),
+			("9", "large"),
+		] {
+			assert_eq!(
+				run(&format!("{}classify({})", classify, input)),
+				Ok(NodeResult::String(output.into()))
+			);
+		}
+		assert_eq!(
+			run("let a = match 3 { x if x > 5 -> 0, x -> x * 2 }\na"),
+			Ok(NodeResult::Int(6))
+		);
+	}
+
+	#[test]
+	fn eval_non_exhaustive_match() {
+		assert_eq!(
+			run("match \"c\" {\n\t\"a\" -> 1\n\t\"b\" -> 2\n}"),
+			Err(RuntimeError::new(
+				"non-exhaustive match: no arm matches `c`",
+				Span::new(6, 9, 1, 6)
+			))
+		);
+	}
+
+	#[test]
+	fn eval_functions() {
+		assert_eq!(
+			run("fun fact(n) {\n\tif n < 2 { return 1 }\n\tn * fact(n - \
+			     1)\n}\nfact(5)"),
+			Ok(NodeResult::Int(120))
+		);
+		assert_eq!(
+			run("let twice = fun (f, x) { f(f(x)) }\ntwice(fun (x) { x * 3 \
+			     }, 2)"),
+			Ok(NodeResult::Int(18))
+		);
+		assert_eq!(run("fun (x) { return }(1)"), Ok(NodeResult::Nil));
+	}
+
+	#[test]
+	fn eval_closures() {
+		assert_eq!(
+			run(
+				"fun counter() {\n\tlet count = 0\n\tfun () { count = count + \
+				 1 }\n}\nlet next = counter()\nnext()\nnext()\nnext()"
+			),
+			Ok(NodeResult::Int(3))
+		);
+		// closures capture the scope itself, not a copy of its values
+		assert_eq!(
+			run("let a = 1\nlet get = fun () { a }\na = 2\nget()"),
+			Ok(NodeResult::Int(2))
+		);
+	}
+
+	#[test]
+	fn eval_call_errors() {
+		assert_eq!(
+			run("fun add(a, b) { a + b }\nadd(1)"),
+			Err(RuntimeError::new(
+				"`add` takes 2 arguments but 1 was supplied",
+				Span::new(24, 30, 2, 0)
+			))
+		);
+		assert_eq!(
+			run("let a = 1\na()").map_err(|err| err.message),
+			Err("cannot call int".into())
+		);
+
+		// test threads have far less stack than the interpreter thread
+		let overflow = thread::Builder::new()
+			.stack_size(STACK_SIZE)
+			.spawn(|| {
+				// recursing through an `if`, a block and a binary expression
+				// takes far more stack per call than `fun f() { f() }`
+				run("fun f(n) { if n < 0 { 0 } else { 1 + f(n + 1) } }\nf(0)")
+					.unwrap_err()
+					.message
+			})
+			.unwrap()
+			.join()
+			.unwrap();
+		assert_eq!(
+			overflow,
+			"stack overflow: calls nested more than 1000 deep"
+		);
+	}
+
+	#[test]
+	fn eval_deep_recursion() {
+		// every call goes through several evaluator frames, which are much
+		// larger in debug builds
+		let depth = |n: usize| {
+			let source = format!(
+				"fun f(n) {{ if n == 0 {{ 0 }} else {{ 1 + f(n - 1) }} \
+				 }}\nf({})",
+				n
+			);
+			thread::Builder::new()
+				.stack_size(STACK_SIZE)
+				.spawn(move || {
+					run(&source)
+						.map(|value| value.to_string())
+						.map_err(|err| err.message)
+				})
+				.unwrap()
+				.join()
+				.unwrap()
+		};
+
+		assert_eq!(depth(900), Ok("900".into()));
+		assert_eq!(
+			depth(100_000),
+			Err("stack overflow: calls nested more than 1000 deep".into())
+		);
+	}
+}
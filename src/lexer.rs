@@ -1,3 +1,7 @@
+use std::fmt;
+
+use unicode_xid::UnicodeXID;
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum TokenKind {
 	// Infix Operators
@@ -22,6 +26,7 @@ pub enum TokenKind {
 	// Prefix Operators
 	BitNot,
 	Not,
+	Range,
 
 	// Keywords
 	True,
@@ -30,7 +35,15 @@ pub enum TokenKind {
 	Match,
 	While,
 	For,
+	In,
+	Break,
+	Continue,
 	Let,
+	If,
+	Else,
+	Return,
+	Import,
+	From,
 
 	// Symbols
 	LParen,
@@ -46,47 +59,283 @@ pub enum TokenKind {
 
 	// Misc
 	String(String),
-	Number(f64),
+	// `"text ${`, `} text ${` and `} text"`, around the tokens of each
+	// expression interpolated into a string
+	TemplateStart(String),
+	TemplateMiddle(String),
+	TemplateEnd(String),
+	Int(i64),
+	Float(f64),
 	Ident(String),
-	Undefined(String),
+	// `'name`, naming a loop
+	Label(String),
+	Error(LexError),
+	// the text of a `//` or `/* */` comment
 	Comment(String),
+	// a line of a `///` comment, documenting the `fun` or `let` after it
+	DocComment(String),
 	Delimeter,
 }
 
+#[derive(PartialEq, Debug, Clone)]
+pub enum LexError {
+	UnexpectedChar(char),
+	// the quotes that open the string, like `"`, `"""` or `r"`
+	UnterminatedString(String),
+	UnterminatedComment,
+	// the escape sequence as written, like `\q` or `\u{110000}`
+	InvalidEscape(String),
+	MalformedNumber(String),
+	// a well formed literal whose value doesn't fit in an int or float
+	NumberTooLarge(String),
+}
+
+impl fmt::Display for LexError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::UnexpectedChar(ch) => {
+				write!(f, "unexpected character {:?}", ch)
+			}
+			Self::UnterminatedString(_) => {
+				write!(f, "unterminated string literal")
+			}
+			Self::UnterminatedComment => {
+				write!(f, "unterminated block comment")
+			}
+			Self::InvalidEscape(escape) => {
+				write!(f, "invalid escape sequence `{}`", escape)
+			}
+			Self::MalformedNumber(str) => {
+				write!(f, "malformed number literal `{}`", str)
+			}
+			Self::NumberTooLarge(str) => {
+				write!(f, "number literal `{}` is too large", str)
+			}
+		}
+	}
+}
+
+impl fmt::Display for TokenKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let symbol = match self {
+			Self::Plus => "+",
+			Self::Minus => "-",
+			Self::Multiply => "*",
+			Self::Divide => "/",
+			Self::Mod => "%",
+			Self::LessEq => "<=",
+			Self::GreaterEq => ">=",
+			Self::Greater => ">",
+			Self::Less => "<",
+			Self::BitAnd => "&",
+			Self::BitOr => "|",
+			Self::Xor => "^",
+			Self::And => "&&",
+			Self::Or => "||",
+			Self::Assign => "=",
+			Self::Eq => "==",
+			Self::NotEq => "!=",
+			Self::BitNot => "~",
+			Self::Not => "!",
+			Self::Range => "..",
+			Self::True => "true",
+			Self::False => "false",
+			Self::Fun => "fun",
+			Self::Match => "match",
+			Self::While => "while",
+			Self::For => "for",
+			Self::In => "in",
+			Self::Break => "break",
+			Self::Continue => "continue",
+			Self::Let => "let",
+			Self::If => "if",
+			Self::Else => "else",
+			Self::Return => "return",
+			Self::Import => "import",
+			Self::From => "from",
+			Self::LParen => "(",
+			Self::RParen => ")",
+			Self::LBrace => "{",
+			Self::RBrace => "}",
+			Self::LBracket => "[",
+			Self::RBracket => "]",
+			Self::Colon => ":",
+			Self::Comma => ",",
+			Self::Period => ".",
+			Self::MatchArm => "->",
+			Self::String(str) => return write!(f, "string \"{}\"", str),
+			Self::TemplateStart(str) => {
+				return write!(f, "string \"{}${{\"", str)
+			}
+			Self::TemplateMiddle(_) | Self::TemplateEnd(_) => "}",
+			Self::Int(num) => return write!(f, "integer `{}`", num),
+			Self::Float(num) => return write!(f, "float `{}`", num),
+			Self::Ident(ident) => return write!(f, "identifier `{}`", ident),
+			Self::Label(label) => return write!(f, "label `'{}`", label),
+			Self::Error(err) => return write!(f, "{}", err),
+			Self::Comment(_) => return write!(f, "comment"),
+			Self::DocComment(_) => return write!(f, "doc comment"),
+			Self::Delimeter => return write!(f, "end of statement"),
+		};
+
+		write!(f, "`{}`", symbol)
+	}
+}
+
+// A region of the source: the byte range `start..end` and the line and
+// column (in characters) it starts at
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct Span {
+	pub(crate) start: usize,
+	pub(crate) end: usize,
+	pub(crate) line: usize,
+	pub(crate) col: usize,
+}
+
+impl Span {
+	pub(crate) fn new(
+		start: usize,
+		end: usize,
+		line: usize,
+		col: usize,
+	) -> Span {
+		Self {
+			start,
+			end,
+			line,
+			col,
+		}
+	}
+
+	// the span covering both `self` and everything up to the end of `other`
+	pub(crate) fn to(self, other: Span) -> Span {
+		Self {
+			end: other.end.max(self.end),
+			..self
+		}
+	}
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub struct Token {
 	pub(crate) kind: TokenKind,
-	pub(crate) span: (usize, usize),
+	pub(crate) span: Span,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct Lexer<'a> {
 	input: &'a str,
-	position: usize,
+	offset: usize,
 	cursor: (usize, usize),
+	// the quotes of every string with an open `${`, innermost last, and how
+	// many braces are open inside that `${`
+	templates: Vec<(&'static str, usize)>,
+}
+
+// Identifiers follow UAX #31, so they start with a letter (of any script) or
+// `_` and continue with letters, digits, `_` and combining marks.
+fn is_ident_start(ch: char) -> bool { ch == '_' || ch.is_xid_start() }
+
+fn is_ident_continue(ch: char) -> bool { ch.is_xid_continue() }
+
+// The value of a number literal: `0x`, `0b` or `0o` and digits in that base,
+// or decimal digits with an optional fraction and exponent. `_` may separate
+// any two digits.
+fn number_value(literal: &str) -> Result<TokenKind, LexError> {
+	let malformed = || LexError::MalformedNumber(literal.into());
+	let too_large = || LexError::NumberTooLarge(literal.into());
+	let (radix, digits) = match literal.get(..2) {
+		Some("0x") => (16, &literal[2..]),
+		Some("0b") => (2, &literal[2..]),
+		Some("0o") => (8, &literal[2..]),
+		_ => (10, literal),
+	};
+
+	let chars = digits.chars().collect::<Vec<_>>();
+	let is_digit = |i: Option<usize>| {
+		i.and_then(|i| chars.get(i))
+			.is_some_and(|ch| ch.is_digit(radix))
+	};
+	let separated = (0..chars.len())
+		.filter(|&i| chars[i] == '_')
+		.all(|i| is_digit(i.checked_sub(1)) && is_digit(Some(i + 1)));
+	if !separated {
+		return Err(malformed());
+	}
+	let digits = digits.replace('_', "");
+
+	if radix != 10 {
+		if digits.is_empty() || !digits.chars().all(|ch| ch.is_digit(radix)) {
+			return Err(malformed());
+		}
+		return i64::from_str_radix(&digits, radix)
+			.map(TokenKind::Int)
+			.map_err(|_| too_large());
+	}
+
+	let (mantissa, exponent) = match digits.find(['e', 'E']) {
+		Some(at) => (&digits[..at], Some(&digits[at + 1..])),
+		None => (&digits[..], None),
+	};
+	let exponent_digits = exponent
+		.map(|exponent| exponent.strip_prefix(['+', '-']).unwrap_or(exponent));
+	if !mantissa.chars().all(|ch| ch.is_ascii_digit() || ch == '.')
+		|| mantissa.matches('.').count() > 1
+		|| exponent_digits.is_some_and(|exponent| {
+			exponent.is_empty()
+				|| !exponent.chars().all(|ch| ch.is_ascii_digit())
+		}) {
+		return Err(malformed());
+	}
+
+	if mantissa.contains('.') || exponent.is_some() {
+		digits
+			.parse()
+			.ok()
+			.filter(|num: &f64| num.is_finite())
+			.map(TokenKind::Float)
+			.ok_or_else(too_large)
+	} else {
+		digits.parse().map(TokenKind::Int).map_err(|_| too_large())
+	}
 }
 
 impl<'a> Lexer<'a> {
 	pub(crate) fn new(input: &'a str) -> Lexer<'a> {
 		Self {
 			input,
-			position: 0,
+			offset: 0,
 			cursor: (1, 0),
+			templates: Vec::new(),
 		}
 	}
 
+	// the source text a token was read from
+	pub(crate) fn text(&self, span: Span) -> &'a str {
+		&self.input[span.start..span.end]
+	}
+
 	fn translate(&mut self, chars: usize) {
-		self.cursor.1 += chars;
-		self.position += chars;
+		for _ in 0..chars {
+			self.offset += self.get_char_raw().map_or(0, char::len_utf8);
+			self.cursor.1 += 1;
+		}
+	}
+
+	// where the next token starts, as a byte offset and a cursor
+	fn mark(&self) -> (usize, (usize, usize)) { (self.offset, self.cursor) }
+
+	fn span(&self, (start, (line, col)): (usize, (usize, usize))) -> Span {
+		Span::new(start, self.offset, line, col)
 	}
 
 	fn n_char_token(&mut self, tok: TokenKind, n: usize) -> Option<Token> {
-		let pos = self.cursor;
+		let start = self.mark();
 		self.translate(n);
 
 		Some(Token {
 			kind: tok,
-			span: pos,
+			span: self.span(start),
 		})
 	}
 
@@ -98,49 +347,214 @@ impl<'a> Lexer<'a> {
 		self.n_char_token(tok, 2)
 	}
 
-	fn get_char_raw(&self, pos: Option<usize>) -> Option<char> {
-		self.input.chars().nth(pos.unwrap_or(self.position))
+	// `offset` is a byte index, so looking at the current character never
+	// rescans the input before it
+	fn get_char_raw(&self) -> Option<char> {
+		self.input[self.offset..].chars().next()
 	}
 
 	fn peek(&self) -> Option<char> {
-		self.get_char_raw(Some(self.position + 1))
+		let mut chars = self.input[self.offset..].chars();
+		chars.next();
+		chars.next()
+	}
+
+	// steps over a newline inside a token, onto the start of the next line
+	fn newline(&mut self) {
+		self.translate(1);
+		self.cursor.0 += 1;
+		self.cursor.1 = 0;
+	}
+
+	// Strings are `"..."`, or `"""..."""` to span several lines, and a
+	// leading `r` turns off escapes and interpolation.
+	fn string(&mut self, raw: bool) -> Option<Token> {
+		let start = self.mark();
+
+		if raw {
+			self.translate(1);
+		}
+		let quotes = if self.input[self.offset..].starts_with("\"\"\"") {
+			"\"\"\""
+		} else {
+			"\""
+		};
+		self.translate(quotes.len());
+
+		self.string_part(start, quotes, raw, true)
 	}
 
-	fn string(&mut self) -> Option<Token> {
+	// The text of a string up to its closing quotes or up to a `${`, which
+	// leaves the lexer reading the interpolated expression until the `}` that
+	// matches it. An invalid escape doesn't end the string, so the rest of it
+	// is still skipped, but the error points at the escape.
+	fn string_part(
+		&mut self,
+		start: (usize, (usize, usize)),
+		quotes: &'static str,
+		raw: bool,
+		first: bool,
+	) -> Option<Token> {
 		let mut str = String::new();
-		let pos = self.cursor;
+		let mut error = None;
+		let mut interpolated = false;
 
-		self.translate(1);
-		while let Some(ch) = self.get_char_raw(None) {
-			match ch {
-				'\\' if self.peek() == Some('"') => {
-					str.push('"');
+		loop {
+			match self.get_char_raw() {
+				_ if self.input[self.offset..].starts_with(quotes) => {
+					self.translate(quotes.len());
+					break;
+				}
+				Some('$') if !raw && self.peek() == Some('{') => {
 					self.translate(2);
+					self.templates.push((quotes, 0));
+					interpolated = true;
+					break;
 				}
-				'"' => {
+				Some('\n') if quotes.len() == 3 => {
+					str.push('\n');
+					self.newline();
+				}
+				// the text of a multi-line string doesn't depend on the line
+				// endings of the file it is in
+				Some('\r')
+					if quotes.len() == 3 && self.peek() == Some('\n') =>
+				{
 					self.translate(1);
-					break;
 				}
-				_ => {
+				Some('\n') | None => {
+					return Some(Token {
+						kind: TokenKind::Error(LexError::UnterminatedString(
+							format!("{}{}", if raw { "r" } else { "" }, quotes),
+						)),
+						span: self.span(start),
+					})
+				}
+				Some('\\') if !raw => match self.escape() {
+					Ok(ch) => str.push(ch),
+					Err(err) => {
+						error = error.or(Some(err));
+					}
+				},
+				Some(ch) => {
 					str.push(ch);
 					self.translate(1);
 				}
 			}
 		}
 
-		Some(Token {
-			kind: TokenKind::String(str),
-			span: pos,
+		let kind = match (first, interpolated) {
+			(true, false) => TokenKind::String(str),
+			(true, true) => TokenKind::TemplateStart(str),
+			(false, true) => TokenKind::TemplateMiddle(str),
+			(false, false) => TokenKind::TemplateEnd(str),
+		};
+
+		Some(match error {
+			Some((err, span)) => Token {
+				kind: TokenKind::Error(err),
+				span,
+			},
+			None => Token {
+				kind,
+				span: self.span(start),
+			},
 		})
 	}
 
+	// the `}` closing an interpolation picks the string back up
+	fn close_brace(&mut self) -> Option<Token> {
+		match self.templates.last_mut() {
+			Some((quotes, 0)) => {
+				let quotes = *quotes;
+				self.templates.pop();
+				let start = self.mark();
+				self.translate(1);
+				self.string_part(start, quotes, false, false)
+			}
+			Some((_, depth)) => {
+				*depth -= 1;
+				self.single_char_token(TokenKind::RBrace)
+			}
+			None => self.single_char_token(TokenKind::RBrace),
+		}
+	}
+
+	// `\n`, `\t`, `\r`, `\0`, `\\`, `\"`, `\$` or `\u{...}` with up to six
+	// hex digits, reporting anything else with the span of the escape
+	fn escape(&mut self) -> Result<char, (LexError, Span)> {
+		let start = self.mark();
+		self.translate(1);
+
+		let ch = match self.get_char_raw() {
+			Some('n') => '\n',
+			Some('t') => '\t',
+			Some('r') => '\r',
+			Some('0') => '\0',
+			Some('\\') => '\\',
+			Some('"') => '"',
+			Some('$') => '$',
+			Some('u') => return self.unicode_escape(start),
+			// the newline or end of input ends the string instead
+			Some('\n') | None => {
+				return Err((
+					LexError::InvalidEscape("\\".into()),
+					self.span(start),
+				))
+			}
+			Some(ch) => {
+				self.translate(1);
+				return Err((
+					LexError::InvalidEscape(format!("\\{}", ch)),
+					self.span(start),
+				));
+			}
+		};
+		self.translate(1);
+
+		Ok(ch)
+	}
+
+	fn unicode_escape(
+		&mut self,
+		start: (usize, (usize, usize)),
+	) -> Result<char, (LexError, Span)> {
+		self.translate(1);
+		let mut digits = String::new();
+		let braced = self.get_char_raw() == Some('{');
+		if braced {
+			self.translate(1);
+			while let Some(ch) = self.get_char_raw() {
+				match ch {
+					'}' => break,
+					'0'..='9' | 'a'..='f' | 'A'..='F' if digits.len() < 6 => {
+						digits.push(ch);
+						self.translate(1);
+					}
+					_ => break,
+				}
+			}
+		}
+
+		let closed = braced && self.get_char_raw() == Some('}');
+		if closed {
+			self.translate(1);
+		}
+		let escape = self.input[start.0..self.offset].to_string();
+		u32::from_str_radix(&digits, 16)
+			.ok()
+			.filter(|_| closed)
+			.and_then(char::from_u32)
+			.ok_or_else(|| (LexError::InvalidEscape(escape), self.span(start)))
+	}
+
 	fn identifier(&mut self) -> Option<Token> {
 		let mut str = String::new();
-		let pos = self.cursor;
+		let start = self.mark();
 
-		while let Some(ch) = self.get_char_raw(None) {
+		while let Some(ch) = self.get_char_raw() {
 			match ch {
-				'A'..='Z' | 'a'..='z' | '0'..='9' | '_' => {
+				ch if is_ident_continue(ch) => {
 					str.push(ch);
 					self.translate(1);
 				}
@@ -155,28 +569,67 @@ impl<'a> Lexer<'a> {
 			"match" => TokenKind::Match,
 			"while" => TokenKind::While,
 			"for" => TokenKind::For,
+			"in" => TokenKind::In,
+			"break" => TokenKind::Break,
+			"continue" => TokenKind::Continue,
 			"let" => TokenKind::Let,
+			"if" => TokenKind::If,
+			"else" => TokenKind::Else,
+			"return" => TokenKind::Return,
+			"import" => TokenKind::Import,
+			"from" => TokenKind::From,
 			_ => TokenKind::Ident(str),
 		};
 
 		Some(Token {
 			kind: tok,
-			span: pos,
+			span: self.span(start),
 		})
 	}
 
-	fn whitespace(&mut self) -> Option<Token> {
+	fn label(&mut self) -> Option<Token> {
+		let mut str = String::new();
+		let start = self.mark();
+
 		self.translate(1);
-		self.next()
+		while let Some(ch) = self.get_char_raw() {
+			match ch {
+				ch if is_ident_continue(ch) => {
+					str.push(ch);
+					self.translate(1);
+				}
+				_ => break,
+			}
+		}
+
+		Some(Token {
+			kind: TokenKind::Label(str),
+			span: self.span(start),
+		})
+	}
+
+	// Skipped in a loop rather than token by token, so a long run of
+	// whitespace can't exhaust the stack. `\r` is whitespace too, so `\r\n`
+	// line endings end a statement at the `\n`, and so is a newline inside an
+	// interpolated expression, which can't end a statement.
+	fn skip_whitespace(&mut self) {
+		while let Some(ch) = self.get_char_raw() {
+			match ch {
+				' ' | '\t' | '\r' => self.translate(1),
+				'\n' if !self.templates.is_empty() => self.newline(),
+				_ => break,
+			}
+		}
 	}
 
 	fn delimeter(&mut self, increment: bool) -> Option<Token> {
+		let start = self.mark();
+		self.translate(1);
+
 		let token = Token {
 			kind: TokenKind::Delimeter,
-			span: self.cursor,
+			span: self.span(start),
 		};
-
-		self.translate(1);
 		if increment {
 			self.cursor.0 += 1;
 			self.cursor.1 = 0;
@@ -185,42 +638,45 @@ impl<'a> Lexer<'a> {
 		Some(token)
 	}
 
+	// Takes everything that could belong to the literal before checking it,
+	// so `0xfg`, `1e` or `1.5.3` is one malformed number rather than a number
+	// followed by an identifier or another number. A `.` only belongs to the
+	// literal when a digit follows it, which leaves ranges like `1..3` alone.
 	fn number(&mut self) -> Option<Token> {
 		let mut str = String::new();
-		let pos = self.cursor;
+		let start = self.mark();
+		let prefixed = self.get_char_raw() == Some('0')
+			&& matches!(self.peek(), Some('x' | 'b' | 'o'));
 
-		while let Some(ch) = self.get_char_raw(None) {
+		while let Some(ch) = self.get_char_raw() {
+			let digit_next = matches!(self.peek(), Some('0'..='9'));
 			match ch {
-				'0'..='9' => {
-					str.push(ch);
-					self.translate(1);
-				}
-				'.' if matches!(self.peek(), Some('0'..='9')) => {
-					str.push(ch);
-					self.translate(1);
-				}
+				ch if is_ident_continue(ch) => {}
+				'.' if digit_next => {}
+				'+' | '-'
+					if !prefixed && str.ends_with(['e', 'E']) && digit_next => {}
 				_ => break,
 			}
+			str.push(ch);
+			self.translate(1);
 		}
 
-		match str.parse() {
-			Ok(num) => Some(Token {
-				kind: TokenKind::Number(num),
-				span: pos,
-			}),
-			_ => Some(Token {
-				kind: TokenKind::Undefined(str),
-				span: pos,
-			}),
-		}
+		Some(Token {
+			kind: number_value(&str).unwrap_or_else(TokenKind::Error),
+			span: self.span(start),
+		})
 	}
 
+	// `//` runs to the end of the line, and `///` (but not `////`) makes it a
+	// doc comment
 	fn comment(&mut self) -> Option<Token> {
 		let mut str = String::new();
-		let pos = self.cursor;
+		let start = self.mark();
+		let rest = &self.input[self.offset..];
+		let doc = rest.starts_with("///") && !rest.starts_with("////");
 
-		self.translate(2);
-		while let Some(ch) = self.get_char_raw(None) {
+		self.translate(if doc { 3 } else { 2 });
+		while let Some(ch) = self.get_char_raw() {
 			match ch {
 				'\n' => {
 					break;
@@ -232,9 +688,71 @@ impl<'a> Lexer<'a> {
 			}
 		}
 
+		// only the space after `///` is dropped, so indentation inside the
+		// doc is kept
+		let kind = if doc {
+			TokenKind::DocComment(
+				str.strip_prefix(' ').unwrap_or(&str).trim_end().into(),
+			)
+		} else {
+			TokenKind::Comment(str.trim().into())
+		};
+
+		Some(Token {
+			kind,
+			span: self.span(start),
+		})
+	}
+
+	// `/* ... */`, which may span several lines and nest, so commenting out
+	// code that already has a block comment in it works
+	fn block_comment(&mut self) -> Option<Token> {
+		let mut str = String::new();
+		let start = self.mark();
+		let mut depth = 0usize;
+
+		loop {
+			let rest = &self.input[self.offset..];
+			if rest.starts_with("/*") {
+				if depth > 0 {
+					str.push_str("/*");
+				}
+				depth += 1;
+				self.translate(2);
+			} else if rest.starts_with("*/") {
+				depth -= 1;
+				self.translate(2);
+				if depth == 0 {
+					break;
+				}
+				str.push_str("*/");
+			} else {
+				match self.get_char_raw() {
+					Some('\n') => {
+						str.push('\n');
+						self.newline();
+					}
+					Some(ch) => {
+						str.push(ch);
+						self.translate(1);
+					}
+					// errors point at the `/*` that was never closed
+					None => {
+						let (offset, (line, col)) = start;
+						return Some(Token {
+							kind: TokenKind::Error(
+								LexError::UnterminatedComment,
+							),
+							span: Span::new(offset, offset + 2, line, col),
+						});
+					}
+				}
+			}
+		}
+
 		Some(Token {
 			kind: TokenKind::Comment(str.trim().into()),
-			span: pos,
+			span: self.span(start),
 		})
 	}
 }
@@ -243,14 +761,16 @@ impl<'a> Iterator for Lexer<'a> {
 	type Item = Token;
 
 	fn next(&mut self) -> Option<Token> {
-		let current_char = self.get_char_raw(None)?;
+		self.skip_whitespace();
+		let current_char = self.get_char_raw()?;
 
 		match current_char {
 			'/' if self.peek() == Some('/') => self.comment(),
-			'"' => self.string(),
+			'/' if self.peek() == Some('*') => self.block_comment(),
+			'"' => self.string(false),
+			'r' if self.peek() == Some('"') => self.string(true),
 			'\n' => self.delimeter(true),
 			';' => self.delimeter(false),
-			' ' | '\t' => self.whitespace(),
 			'=' if self.peek() == Some('=') => {
 				self.double_char_token(TokenKind::Eq)
 			}
@@ -260,8 +780,13 @@ impl<'a> Iterator for Lexer<'a> {
 			'=' => self.single_char_token(TokenKind::Assign),
 			'(' => self.single_char_token(TokenKind::LParen),
 			')' => self.single_char_token(TokenKind::RParen),
-			'{' => self.single_char_token(TokenKind::LBrace),
-			'}' => self.single_char_token(TokenKind::RBrace),
+			'{' => {
+				if let Some((_, depth)) = self.templates.last_mut() {
+					*depth += 1;
+				}
+				self.single_char_token(TokenKind::LBrace)
+			}
+			'}' => self.close_brace(),
 			'[' => self.single_char_token(TokenKind::LBracket),
 			']' => self.single_char_token(TokenKind::RBracket),
 			'-' if self.peek() == Some('>') => {
@@ -293,11 +818,16 @@ impl<'a> Iterator for Lexer<'a> {
 			'!' => self.single_char_token(TokenKind::Not),
 			':' => self.single_char_token(TokenKind::Colon),
 			',' => self.single_char_token(TokenKind::Comma),
+			'.' if self.peek() == Some('.') => {
+				self.double_char_token(TokenKind::Range)
+			}
 			'.' => self.single_char_token(TokenKind::Period),
-			'A'..='Z' | 'a'..='z' => self.identifier(),
+			'\'' if self.peek().is_some_and(is_ident_start) => self.label(),
 			'0'..='9' => self.number(),
-			_ => self
-				.single_char_token(TokenKind::Undefined(current_char.into())),
+			ch if is_ident_start(ch) => self.identifier(),
+			_ => self.single_char_token(TokenKind::Error(
+				LexError::UnexpectedChar(current_char),
+			)),
 		}
 	}
 }
@@ -314,7 +844,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Delimeter,
-				span: (1, 0)
+				span: Span::new(0, 1, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -329,21 +859,87 @@ mod test {
 			vec![
 				Token {
 					kind: TokenKind::Comment("hello world!".into()),
-					span: (1, 0)
+					span: Span::new(0, 14, 1, 0)
 				},
 				Token {
 					kind: TokenKind::Delimeter,
-					span: (1, 14)
+					span: Span::new(14, 15, 1, 14)
 				},
 				Token {
 					kind: TokenKind::Comment("test!".into()),
-					span: (2, 0)
+					span: Span::new(15, 23, 2, 0)
 				},
 			],
 			lexer
 		)
 	}
 
+	#[test]
+	fn lex_block_comment() {
+		let input = "a /* one\n/* two */\n*/ b\n/**/";
+		let lexer = Lexer::new(input).collect::<Vec<_>>();
+
+		assert_eq!(
+			vec![
+				Token {
+					kind: TokenKind::Ident("a".into()),
+					span: Span::new(0, 1, 1, 0)
+				},
+				Token {
+					kind: TokenKind::Comment("one\n/* two */".into()),
+					span: Span::new(2, 21, 1, 2)
+				},
+				Token {
+					kind: TokenKind::Ident("b".into()),
+					span: Span::new(22, 23, 3, 3)
+				},
+				Token {
+					kind: TokenKind::Delimeter,
+					span: Span::new(23, 24, 3, 4)
+				},
+				Token {
+					kind: TokenKind::Comment("".into()),
+					span: Span::new(24, 28, 4, 0)
+				},
+			],
+			lexer
+		)
+	}
+
+	#[test]
+	fn lex_unterminated_block_comment() {
+		let input = "a /* b /* c */\nd";
+		let lexer = Lexer::new(input).collect::<Vec<_>>();
+
+		assert_eq!(
+			Token {
+				kind: TokenKind::Error(LexError::UnterminatedComment),
+				span: Span::new(2, 4, 1, 2)
+			},
+			lexer[1]
+		)
+	}
+
+	#[test]
+	fn lex_doc_comment() {
+		let input = "///  indented\n/// text \n//// plain";
+		let lexer = Lexer::new(input).collect::<Vec<_>>();
+
+		assert_eq!(
+			vec![
+				TokenKind::DocComment(" indented".into()),
+				TokenKind::Delimeter,
+				TokenKind::DocComment("text".into()),
+				TokenKind::Delimeter,
+				TokenKind::Comment("// plain".into()),
+			],
+			lexer
+				.into_iter()
+				.map(|token| token.kind)
+				.collect::<Vec<_>>()
+		)
+	}
+
 	#[test]
 	fn lex_string() {
 		let input = " \"hello!\"";
@@ -352,12 +948,227 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::String("hello!".into()),
-				span: (1, 1)
+				span: Span::new(1, 9, 1, 1)
 			},
 			lexer.next().unwrap()
 		)
 	}
 
+	#[test]
+	fn lex_string_escapes() {
+		let input = r#""a\n\t\\\"\u{1F600}\0" "\\""#;
+
+		assert_eq!(
+			Lexer::new(input)
+				.map(|token| token.kind)
+				.collect::<Vec<_>>(),
+			vec![
+				TokenKind::String("a\n\t\\\"😀\0".into()),
+				TokenKind::String("\\".into()),
+			]
+		)
+	}
+
+	#[test]
+	fn lex_raw_string() {
+		let input = r#"r"a\nb\" x"#;
+		let lexer = Lexer::new(input).collect::<Vec<_>>();
+
+		assert_eq!(
+			Token {
+				kind: TokenKind::String(r"a\nb\".into()),
+				span: Span::new(0, 8, 1, 0)
+			},
+			lexer[0]
+		);
+		assert_eq!(lexer[1].kind, TokenKind::Ident("x".into()));
+	}
+
+	#[test]
+	fn lex_multiline_string() {
+		let input = "\"\"\"a\n\"b\"\n\"\"\" x";
+		let lexer = Lexer::new(input).collect::<Vec<_>>();
+
+		assert_eq!(
+			lexer,
+			vec![
+				Token {
+					kind: TokenKind::String("a\n\"b\"\n".into()),
+					span: Span::new(0, 12, 1, 0)
+				},
+				Token {
+					kind: TokenKind::Ident("x".into()),
+					span: Span::new(13, 14, 3, 4)
+				},
+			]
+		)
+	}
+
+	#[test]
+	fn lex_interpolation() {
+		let input = "\"a ${ {b: \"}${c}\"} } d\"\n";
+
+		assert_eq!(
+			Lexer::new(input)
+				.map(|token| token.kind)
+				.collect::<Vec<_>>(),
+			vec![
+				TokenKind::TemplateStart("a ".into()),
+				TokenKind::LBrace,
+				TokenKind::Ident("b".into()),
+				TokenKind::Colon,
+				TokenKind::TemplateStart("}".into()),
+				TokenKind::Ident("c".into()),
+				TokenKind::TemplateEnd("".into()),
+				TokenKind::RBrace,
+				TokenKind::TemplateEnd(" d".into()),
+				TokenKind::Delimeter,
+			]
+		)
+	}
+
+	#[test]
+	fn lex_invalid_escape() {
+		let input = r#""a\qb" + "\u{110000}" + "\u{zz}""#;
+		let lexer = Lexer::new(input).collect::<Vec<_>>();
+
+		assert_eq!(
+			lexer
+				.iter()
+				.map(|token| (token.kind.clone(), token.span))
+				.collect::<Vec<_>>(),
+			vec![
+				(
+					TokenKind::Error(LexError::InvalidEscape(r"\q".into())),
+					Span::new(2, 4, 1, 2)
+				),
+				(TokenKind::Plus, Span::new(7, 8, 1, 7)),
+				(
+					TokenKind::Error(LexError::InvalidEscape(
+						r"\u{110000}".into()
+					)),
+					Span::new(10, 20, 1, 10)
+				),
+				(TokenKind::Plus, Span::new(22, 23, 1, 22)),
+				(
+					TokenKind::Error(LexError::InvalidEscape(r"\u{".into())),
+					Span::new(25, 28, 1, 25)
+				),
+			]
+		)
+	}
+
+	#[test]
+	fn lex_string_ends_at_newline() {
+		let input = "\"ab\ncd";
+		let lexer = Lexer::new(input).collect::<Vec<_>>();
+
+		assert_eq!(
+			Token {
+				kind: TokenKind::Error(LexError::UnterminatedString(
+					"\"".into()
+				)),
+				span: Span::new(0, 3, 1, 0)
+			},
+			lexer[0]
+		);
+		assert_eq!(lexer[2].kind, TokenKind::Ident("cd".into()));
+	}
+
+	#[test]
+	fn lex_unterminated_string() {
+		let input = "let a = \"hello";
+		let lexer = Lexer::new(input).collect::<Vec<_>>();
+
+		assert_eq!(
+			Token {
+				kind: TokenKind::Error(LexError::UnterminatedString(
+					"\"".into()
+				)),
+				span: Span::new(8, 14, 1, 8)
+			},
+			lexer[3]
+		);
+
+		for (input, quotes) in [
+			("\"\"\"a\nb", "\"\"\""),
+			("r\"a", "r\""),
+			("r\"\"\"a\"", "r\"\"\""),
+			("\"a ${b} c", "\""),
+		] {
+			assert_eq!(
+				Lexer::new(input).last().unwrap().kind,
+				TokenKind::Error(LexError::UnterminatedString(quotes.into()))
+			);
+		}
+	}
+
+	#[test]
+	fn lex_unexpected_char() {
+		let input = "a @ b";
+		let lexer = Lexer::new(input).collect::<Vec<_>>();
+
+		assert_eq!(
+			Token {
+				kind: TokenKind::Error(LexError::UnexpectedChar('@')),
+				span: Span::new(2, 3, 1, 2)
+			},
+			lexer[1]
+		)
+	}
+
+	#[test]
+	fn lex_spans() {
+		let input = "\"héllo\" >= long_name";
+		let lexer = Lexer::new(input).collect::<Vec<_>>();
+
+		assert_eq!(
+			vec![
+				Token {
+					kind: TokenKind::String("héllo".into()),
+					span: Span::new(0, 8, 1, 0)
+				},
+				Token {
+					kind: TokenKind::GreaterEq,
+					span: Span::new(9, 11, 1, 8)
+				},
+				Token {
+					kind: TokenKind::Ident("long_name".into()),
+					span: Span::new(12, 21, 1, 11)
+				},
+			],
+			lexer
+		)
+	}
+
+	// run with `cargo test --release -- --ignored lex_scales_linearly`
+	#[test]
+	#[ignore]
+	fn lex_scales_linearly() {
+		use std::time::Instant;
+
+		let line = "let héllo = add_one(2) * 3.5 >= \"wörld\" // comment\n";
+		let time = |lines: usize| {
+			let input = line.repeat(lines);
+			let start = Instant::now();
+			let count = Lexer::new(&input).count();
+			(start.elapsed(), count)
+		};
+
+		let (small, small_count) = time(5_000);
+		let (large, large_count) = time(40_000);
+		let ratio = large.as_secs_f64() / small.as_secs_f64();
+		println!(
+			"5k lines: {:?}, 40k lines: {:?}, ratio: {:.1}",
+			small, large, ratio
+		);
+
+		assert_eq!(small_count * 8, large_count);
+		// 8x the input should take roughly 8x the time; a quadratic lexer
+		// would be closer to 64x
+		assert!(ratio < 20.0, "lexing is not linear: {:.1}x", ratio);
+	}
+
 	#[test]
 	fn lex_whitespace() {
 		let input = "     \t   \t";
@@ -366,6 +1177,50 @@ mod test {
 		assert_eq!(None, lexer.next())
 	}
 
+	#[test]
+	fn lex_long_whitespace() {
+		let input = format!("a{}b", " ".repeat(3_000_000));
+
+		assert_eq!(
+			Lexer::new(&input)
+				.map(|token| token.kind)
+				.collect::<Vec<_>>(),
+			vec![TokenKind::Ident("a".into()), TokenKind::Ident("b".into())]
+		)
+	}
+
+	#[test]
+	fn lex_crlf() {
+		let input = "a // one\r\n\"\"\"b\r\nc\"\"\"\r\n";
+		let lexer = Lexer::new(input).collect::<Vec<_>>();
+
+		assert_eq!(
+			vec![
+				Token {
+					kind: TokenKind::Ident("a".into()),
+					span: Span::new(0, 1, 1, 0)
+				},
+				Token {
+					kind: TokenKind::Comment("one".into()),
+					span: Span::new(2, 9, 1, 2)
+				},
+				Token {
+					kind: TokenKind::Delimeter,
+					span: Span::new(9, 10, 1, 9)
+				},
+				Token {
+					kind: TokenKind::String("b\nc".into()),
+					span: Span::new(10, 20, 2, 0)
+				},
+				Token {
+					kind: TokenKind::Delimeter,
+					span: Span::new(21, 22, 3, 5)
+				},
+			],
+			lexer
+		)
+	}
+
 	#[test]
 	fn lex_equate() {
 		let input = "  ==";
@@ -374,7 +1229,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Eq,
-				span: (1, 2)
+				span: Span::new(2, 4, 1, 2)
 			},
 			lexer.next().unwrap()
 		)
@@ -388,7 +1243,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::NotEq,
-				span: (1, 0)
+				span: Span::new(0, 2, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -402,7 +1257,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Assign,
-				span: (1, 1)
+				span: Span::new(1, 2, 1, 1)
 			},
 			lexer.next().unwrap()
 		)
@@ -417,11 +1272,11 @@ mod test {
 			vec![
 				Token {
 					kind: TokenKind::LParen,
-					span: (1, 0)
+					span: Span::new(0, 1, 1, 0)
 				},
 				Token {
 					kind: TokenKind::RParen,
-					span: (1, 1)
+					span: Span::new(1, 2, 1, 1)
 				}
 			],
 			lexer
@@ -437,11 +1292,11 @@ mod test {
 			vec![
 				Token {
 					kind: TokenKind::LBrace,
-					span: (1, 0)
+					span: Span::new(0, 1, 1, 0)
 				},
 				Token {
 					kind: TokenKind::RBrace,
-					span: (1, 1)
+					span: Span::new(1, 2, 1, 1)
 				}
 			],
 			lexer
@@ -457,11 +1312,11 @@ mod test {
 			vec![
 				Token {
 					kind: TokenKind::LBracket,
-					span: (1, 0)
+					span: Span::new(0, 1, 1, 0)
 				},
 				Token {
 					kind: TokenKind::RBracket,
-					span: (1, 1)
+					span: Span::new(1, 2, 1, 1)
 				}
 			],
 			lexer
@@ -477,11 +1332,11 @@ mod test {
 			vec![
 				Token {
 					kind: TokenKind::MatchArm,
-					span: (1, 0)
+					span: Span::new(0, 2, 1, 0)
 				},
 				Token {
 					kind: TokenKind::MatchArm,
-					span: (1, 3)
+					span: Span::new(3, 5, 1, 3)
 				}
 			],
 			lexer
@@ -496,7 +1351,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Plus,
-				span: (1, 0)
+				span: Span::new(0, 1, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -510,7 +1365,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Minus,
-				span: (1, 0)
+				span: Span::new(0, 1, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -524,7 +1379,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Multiply,
-				span: (1, 0)
+				span: Span::new(0, 1, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -538,7 +1393,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Divide,
-				span: (1, 0)
+				span: Span::new(0, 1, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -552,7 +1407,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Mod,
-				span: (1, 0)
+				span: Span::new(0, 1, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -566,7 +1421,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Greater,
-				span: (1, 0)
+				span: Span::new(0, 1, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -580,7 +1435,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Less,
-				span: (1, 0)
+				span: Span::new(0, 1, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -594,7 +1449,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::GreaterEq,
-				span: (1, 0)
+				span: Span::new(0, 2, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -608,7 +1463,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::LessEq,
-				span: (1, 0)
+				span: Span::new(0, 2, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -622,7 +1477,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::BitOr,
-				span: (1, 0)
+				span: Span::new(0, 1, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -636,7 +1491,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::BitAnd,
-				span: (1, 0)
+				span: Span::new(0, 1, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -650,7 +1505,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::BitNot,
-				span: (1, 0)
+				span: Span::new(0, 1, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -664,7 +1519,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Or,
-				span: (1, 0)
+				span: Span::new(0, 2, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -678,7 +1533,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::And,
-				span: (1, 0)
+				span: Span::new(0, 2, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -692,7 +1547,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Not,
-				span: (1, 0)
+				span: Span::new(0, 1, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -706,7 +1561,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Colon,
-				span: (1, 0)
+				span: Span::new(0, 1, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -720,7 +1575,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Comma,
-				span: (1, 0)
+				span: Span::new(0, 1, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -734,12 +1589,52 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Period,
-				span: (1, 0)
+				span: Span::new(0, 1, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
 	}
 
+	#[test]
+	fn lex_range() {
+		let input = "0..10";
+		let mut lexer = Lexer::new(input);
+
+		assert_eq!(
+			vec![
+				Token {
+					kind: TokenKind::Int(0),
+					span: Span::new(0, 1, 1, 0)
+				},
+				Token {
+					kind: TokenKind::Range,
+					span: Span::new(1, 3, 1, 1)
+				},
+				Token {
+					kind: TokenKind::Int(10),
+					span: Span::new(3, 5, 1, 3)
+				},
+			],
+			lexer.by_ref().collect::<Vec<_>>()
+		)
+	}
+
+	#[test]
+	fn lex_label() {
+		let input = "'outer: break 'outer";
+		let mut lexer = Lexer::new(input);
+
+		assert_eq!(
+			vec![
+				TokenKind::Label("outer".into()),
+				TokenKind::Colon,
+				TokenKind::Break,
+				TokenKind::Label("outer".into()),
+			],
+			lexer.by_ref().map(|token| token.kind).collect::<Vec<_>>()
+		)
+	}
+
 	#[test]
 	fn lex_ident() {
 		let input = "br_uh";
@@ -748,7 +1643,55 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Ident("br_uh".into()),
-				span: (1, 0)
+				span: Span::new(0, 5, 1, 0)
+			},
+			lexer.next().unwrap()
+		)
+	}
+
+	#[test]
+	fn lex_unicode_ident() {
+		let input = "_größe + 変数 'ñ €";
+		let lexer = Lexer::new(input).collect::<Vec<_>>();
+
+		assert_eq!(
+			vec![
+				Token {
+					kind: TokenKind::Ident("_größe".into()),
+					span: Span::new(0, 8, 1, 0)
+				},
+				Token {
+					kind: TokenKind::Plus,
+					span: Span::new(9, 10, 1, 7)
+				},
+				Token {
+					kind: TokenKind::Ident("変数".into()),
+					span: Span::new(11, 17, 1, 9)
+				},
+				Token {
+					kind: TokenKind::Label("ñ".into()),
+					span: Span::new(18, 21, 1, 12)
+				},
+				Token {
+					kind: TokenKind::Error(LexError::UnexpectedChar('€')),
+					span: Span::new(22, 25, 1, 15)
+				},
+			],
+			lexer
+		)
+	}
+
+	#[test]
+	fn lex_ident_combining_marks() {
+		// `e` followed by a combining acute accent, which can only continue an
+		// identifier
+		let input = "cafe\u{301}";
+		let mut lexer = Lexer::new(input);
+
+		assert_eq!(
+			Token {
+				kind: TokenKind::Ident("cafe\u{301}".into()),
+				span: Span::new(0, 6, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -762,7 +1705,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::True,
-				span: (1, 0)
+				span: Span::new(0, 4, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -776,7 +1719,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::False,
-				span: (1, 0)
+				span: Span::new(0, 5, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -790,7 +1733,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Fun,
-				span: (1, 0)
+				span: Span::new(0, 3, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -804,7 +1747,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Match,
-				span: (1, 0)
+				span: Span::new(0, 5, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -818,7 +1761,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::While,
-				span: (1, 0)
+				span: Span::new(0, 5, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -832,7 +1775,7 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::For,
-				span: (1, 0)
+				span: Span::new(0, 3, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -846,7 +1789,77 @@ mod test {
 		assert_eq!(
 			Token {
 				kind: TokenKind::Let,
-				span: (1, 0)
+				span: Span::new(0, 3, 1, 0)
+			},
+			lexer.next().unwrap()
+		)
+	}
+
+	#[test]
+	fn lex_if() {
+		let input = "if";
+		let mut lexer = Lexer::new(input);
+
+		assert_eq!(
+			Token {
+				kind: TokenKind::If,
+				span: Span::new(0, 2, 1, 0)
+			},
+			lexer.next().unwrap()
+		)
+	}
+
+	#[test]
+	fn lex_else() {
+		let input = "else";
+		let mut lexer = Lexer::new(input);
+
+		assert_eq!(
+			Token {
+				kind: TokenKind::Else,
+				span: Span::new(0, 4, 1, 0)
+			},
+			lexer.next().unwrap()
+		)
+	}
+
+	#[test]
+	fn lex_return() {
+		let input = "return";
+		let mut lexer = Lexer::new(input);
+
+		assert_eq!(
+			Token {
+				kind: TokenKind::Return,
+				span: Span::new(0, 6, 1, 0)
+			},
+			lexer.next().unwrap()
+		)
+	}
+
+	#[test]
+	fn lex_import() {
+		let input = "import";
+		let mut lexer = Lexer::new(input);
+
+		assert_eq!(
+			Token {
+				kind: TokenKind::Import,
+				span: Span::new(0, 6, 1, 0)
+			},
+			lexer.next().unwrap()
+		)
+	}
+
+	#[test]
+	fn lex_from() {
+		let input = "from";
+		let mut lexer = Lexer::new(input);
+
+		assert_eq!(
+			Token {
+				kind: TokenKind::From,
+				span: Span::new(0, 4, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -859,13 +1872,72 @@ mod test {
 
 		assert_eq!(
 			Token {
-				kind: TokenKind::Number(123.03),
-				span: (1, 2)
+				kind: TokenKind::Float(123.03),
+				span: Span::new(2, 8, 1, 2)
 			},
 			lexer.next().unwrap()
 		)
 	}
 
+	#[test]
+	fn lex_number_forms() {
+		let input = "0xff 0b1010 0o17 1_000_000 1.5e-3 2E10 1e+2 007";
+
+		assert_eq!(
+			Lexer::new(input)
+				.map(|token| token.kind)
+				.collect::<Vec<_>>(),
+			vec![
+				TokenKind::Int(255),
+				TokenKind::Int(10),
+				TokenKind::Int(15),
+				TokenKind::Int(1_000_000),
+				TokenKind::Float(1.5e-3),
+				TokenKind::Float(2e10),
+				TokenKind::Float(100.),
+				TokenKind::Int(7),
+			]
+		)
+	}
+
+	#[test]
+	fn lex_malformed_number() {
+		let input = "0xfg + 1";
+		let lexer = Lexer::new(input).collect::<Vec<_>>();
+
+		assert_eq!(
+			Token {
+				kind: TokenKind::Error(LexError::MalformedNumber(
+					"0xfg".into()
+				)),
+				span: Span::new(0, 4, 1, 0)
+			},
+			lexer[0]
+		);
+		assert_eq!(lexer[1].kind, TokenKind::Plus);
+
+		for input in [
+			"0b", "0b12", "1__0", "1_", "1_.5", "1e", "12ab", "1.5.3", "0x1.5",
+			"1e5.3",
+		] {
+			assert_eq!(
+				Lexer::new(input)
+					.map(|token| token.kind)
+					.collect::<Vec<_>>(),
+				vec![TokenKind::Error(LexError::MalformedNumber(input.into()))]
+			);
+		}
+		for input in ["9223372036854775808", "0x1_0000_0000_0000_0000", "1e999"]
+		{
+			assert_eq!(
+				Lexer::new(input)
+					.map(|token| token.kind)
+					.collect::<Vec<_>>(),
+				vec![TokenKind::Error(LexError::NumberTooLarge(input.into()))]
+			);
+		}
+	}
+
 	#[test]
 	fn lex_int() {
 		let input = "142 ";
@@ -873,8 +1945,8 @@ mod test {
 
 		assert_eq!(
 			Token {
-				kind: TokenKind::Number(142.),
-				span: (1, 0)
+				kind: TokenKind::Int(142),
+				span: Span::new(0, 3, 1, 0)
 			},
 			lexer.next().unwrap()
 		)
@@ -2,31 +2,103 @@
 
 //! The Delta Programming Language
 
-use std::{env, fs, io};
+use std::{env, fs, io, process, thread};
 
 mod ast;
+mod checker;
+mod diagnostics;
+mod environment;
+mod eval;
 mod lexer;
+mod module;
 mod parser;
 mod repl;
+mod resolver;
+mod stdlib;
+mod token_stream;
 
+use checker::Checker;
+use diagnostics::Diagnostic;
+use eval::Interpreter;
 use lexer::Lexer;
+use module::Source;
 use parser::Parser;
+use resolver::Resolver;
 
 fn main() -> io::Result<()> {
-	let mut args: Vec<String> = env::args().collect();
+	// the evaluator recurses along with the program it runs, so it gets a
+	// thread with room for deeply nested calls
+	thread::Builder::new()
+		.stack_size(eval::STACK_SIZE)
+		.spawn(start)?
+		.join()
+		.expect("the interpreter thread panicked")
+}
+
+fn start() -> io::Result<()> {
+	let mut args: Vec<String> = env::args().skip(1).collect();
+
+	// strict mode refuses to run programs that have warnings
+	let strict = args.iter().any(|arg| arg == "--strict");
+	args.retain(|arg| arg != "--strict");
 
 	match args.len() {
-		1 => {
-			repl::repl()?;
+		0 => {
+			repl::repl(strict)?;
 		}
 		_ => {
-			args.remove(0);
 			for file in args {
 				let content = fs::read_to_string(&file)?;
-				let lexer = Lexer::new(content.trim());
-				let stack = Parser::new(lexer).parse();
+				let lexer = Lexer::new(&content);
+				let tree = match Parser::new(lexer).parse() {
+					Ok(tree) => tree,
+					Err(errors) => {
+						diagnostics::emit(
+							errors.iter().map(Diagnostic::from),
+							&file,
+							&content,
+						);
+						process::exit(1);
+					}
+				};
+
+				if let Err(errors) = Resolver::new(Vec::new()).resolve(&tree) {
+					diagnostics::emit(
+						errors.iter().map(Diagnostic::from),
+						&file,
+						&content,
+					);
+					process::exit(1);
+				}
+
+				let warnings = Checker::new().check(&tree);
+				diagnostics::emit(
+					warnings.iter().map(Diagnostic::from),
+					&file,
+					&content,
+				);
+				if strict && !warnings.is_empty() {
+					eprintln!(
+						"error: not running `{}` due to warnings in strict \
+						 mode",
+						file
+					);
+					process::exit(1);
+				}
 
-				println!("{:#?}", stack);
+				let mut interpreter = Interpreter::new();
+				interpreter.set_strict(strict);
+				interpreter.set_source(Source::new(
+					&file,
+					&file,
+					content.clone(),
+				));
+				let result = interpreter.run(&tree);
+				diagnostics::emit_sourced(&interpreter.take_warnings());
+				if let Err(err) = result {
+					diagnostics::emit_runtime(&err, &file, &content);
+					process::exit(1);
+				}
 			}
 		}
 	}
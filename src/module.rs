@@ -0,0 +1,278 @@
+use std::{
+	path::{Path, PathBuf},
+	rc::Rc,
+};
+
+use crate::{
+	ast::Node, checker::Checker, diagnostics::Diagnostic, lexer::Lexer,
+	parser::Parser, resolver::Resolver,
+};
+
+// A file of source code. Everything that runs remembers the source it came
+// from, so errors can be shown against the right file even when they happen
+// inside an imported module.
+#[derive(Debug, PartialEq)]
+pub struct Source {
+	pub(crate) name: String,
+	pub(crate) path: PathBuf,
+	pub(crate) text: String,
+}
+
+impl Source {
+	pub fn new(
+		name: impl Into<String>,
+		path: impl Into<PathBuf>,
+		text: impl Into<String>,
+	) -> Rc<Source> {
+		Rc::new(Self {
+			name: name.into(),
+			path: path.into(),
+			text: text.into(),
+		})
+	}
+
+	// where the paths this source imports are looked up
+	pub(crate) fn dir(&self) -> &Path {
+		self.path.parent().unwrap_or_else(|| Path::new(""))
+	}
+}
+
+// parses, resolves and checks an imported module, which has no globals of its
+// own beyond what it declares or imports, returning its tree and warnings
+pub(crate) fn parse(
+	source: &Source,
+) -> Result<(Vec<Node>, Vec<Diagnostic>), Vec<Diagnostic>> {
+	let tree =
+		Parser::new(Lexer::new(&source.text))
+			.parse()
+			.map_err(|errors| {
+				errors.iter().map(Diagnostic::from).collect::<Vec<_>>()
+			})?;
+
+	Resolver::new(Vec::new()).resolve(&tree).map_err(|errors| {
+		errors.iter().map(Diagnostic::from).collect::<Vec<_>>()
+	})?;
+
+	let warnings = Checker::new()
+		.check(&tree)
+		.iter()
+		.map(Diagnostic::from)
+		.collect();
+
+	Ok((tree, warnings))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	use std::{env, fs, process};
+
+	use crate::eval::{Interpreter, NodeResult, RuntimeError};
+
+	fn run(
+		test: &str,
+		files: &[(&str, &str)],
+	) -> Result<NodeResult, RuntimeError> {
+		load(test, files, false).0
+	}
+
+	// writes `files` into a fresh directory and runs the first one, along
+	// with the files and messages of the warnings of its imports
+	fn load(
+		test: &str,
+		files: &[(&str, &str)],
+		strict: bool,
+	) -> (Result<NodeResult, RuntimeError>, Vec<(String, String)>) {
+		let dir =
+			env::temp_dir().join(format!("delta-{}-{}", process::id(), test));
+		for (name, text) in files {
+			let path = dir.join(name);
+			fs::create_dir_all(path.parent().unwrap()).unwrap();
+			fs::write(path, text).unwrap();
+		}
+
+		let (name, text) = files[0];
+		let tree = Parser::new(Lexer::new(text)).parse().unwrap();
+		let mut interpreter = Interpreter::new();
+		interpreter.set_strict(strict);
+		interpreter.set_source(Source::new(name, dir.join(name), text));
+		let result = interpreter.run(&tree);
+		let warnings = interpreter
+			.take_warnings()
+			.iter()
+			.map(|(source, diagnostic)| {
+				(source.name.clone(), diagnostic.message.clone())
+			})
+			.collect();
+
+		fs::remove_dir_all(dir).unwrap();
+		(result, warnings)
+	}
+
+	#[test]
+	fn import_relative_paths() {
+		assert_eq!(
+			run(
+				"import_relative_paths",
+				&[
+					(
+						"main.dt",
+						"import { twice, one } from \
+						 \"lib/math.dt\"\ntwice(one)",
+					),
+					(
+						"lib/math.dt",
+						"import { one } from \"one.dt\"\nfun twice(n) { n * 2 \
+						 }",
+					),
+					("lib/one.dt", "let one = 1"),
+				]
+			),
+			Ok(NodeResult::Int(2))
+		);
+	}
+
+	#[test]
+	fn import_once() {
+		// both imports see the same module, so they share its state
+		assert_eq!(
+			run(
+				"import_once",
+				&[
+					(
+						"main.dt",
+						"import { count } from \"count.dt\"\nimport { bump } \
+						 from \"bump.dt\"\nbump()\nbump()\ncount.n",
+					),
+					(
+						"bump.dt",
+						"import { count } from \"count.dt\"\nfun bump() { \
+						 count.n = count.n + 1 }",
+					),
+					("count.dt", "let count = { n: 0 }"),
+				]
+			),
+			Ok(NodeResult::Int(2))
+		);
+	}
+
+	#[test]
+	fn import_cycle() {
+		let err = run(
+			"import_cycle",
+			&[
+				("main.dt", "import { a } from \"a.dt\""),
+				("a.dt", "import { b } from \"b.dt\"\nlet a = 1"),
+				("b.dt", "import { a } from \"a.dt\"\nlet b = 2"),
+			],
+		)
+		.unwrap_err();
+
+		assert_eq!(
+			(err.message.as_str(), err.source.unwrap().name.as_str()),
+			("import cycle: `a.dt` -> `b.dt` -> `a.dt`", "b.dt")
+		);
+	}
+
+	#[test]
+	fn import_cycle_to_entry() {
+		// the cycle is found the first time `main.dt` is imported, before any
+		// of it runs again
+		let err = run(
+			"import_cycle_to_entry",
+			&[
+				("main.dt", "import { b } from \"b.dt\"\nlet main = 1"),
+				("b.dt", "import { main } from \"main.dt\"\nlet b = 2"),
+			],
+		)
+		.unwrap_err();
+		assert_eq!(
+			(err.message.as_str(), err.source.unwrap().name.as_str()),
+			("import cycle: `main.dt` -> `b.dt` -> `main.dt`", "b.dt")
+		);
+
+		assert_eq!(
+			run(
+				"import_self",
+				&[("main.dt", "let x = 1\nimport { x } from \"main.dt\"")]
+			)
+			.map_err(|err| err.message),
+			Err("import cycle: `main.dt` -> `main.dt`".into())
+		);
+	}
+
+	#[test]
+	fn import_errors() {
+		let err = run(
+			"import_errors",
+			&[
+				("main.dt", "import { a } from \"a.dt\""),
+				("a.dt", "let a = (1"),
+			],
+		)
+		.unwrap_err();
+		assert_eq!(err.message, "could not load module `a.dt`");
+		assert_eq!(
+			err.related
+				.iter()
+				.map(|(source, diagnostic)| (
+					source.name.as_str(),
+					diagnostic.message.as_str()
+				))
+				.collect::<Vec<_>>(),
+			vec![("a.dt", "expected `)`, found end of input")]
+		);
+
+		assert_eq!(
+			run(
+				"import_missing",
+				&[
+					("main.dt", "import { b } from \"a.dt\""),
+					("a.dt", "let a = 1")
+				]
+			)
+			.map_err(|err| err.message),
+			Err("module `a.dt` has no `b`".into())
+		);
+		assert_eq!(
+			run("import_builtin", &[("main.dt", "import { sdt }")])
+				.map_err(|err| err.message),
+			Err("no built-in module named `sdt`".into())
+		);
+	}
+
+	#[test]
+	fn import_warnings() {
+		let files = [
+			("main.dt", "import { f } from \"f.dt\"\nf(true)"),
+			("f.dt", "fun f(b) { match b { true -> 1 } }"),
+		];
+		assert_eq!(
+			load("import_warnings", &files, false),
+			(
+				Ok(NodeResult::Int(1)),
+				vec![(
+					"f.dt".into(),
+					"non-exhaustive match: `false` not covered".into()
+				)]
+			)
+		);
+
+		let err = load("import_warnings_strict", &files, true).0.unwrap_err();
+		assert_eq!(
+			err.message,
+			"not loading module `f.dt` due to warnings in strict mode"
+		);
+		assert_eq!(
+			err.related
+				.iter()
+				.map(|(source, diagnostic)| (
+					source.name.as_str(),
+					diagnostic.message.as_str()
+				))
+				.collect::<Vec<_>>(),
+			vec![("f.dt", "non-exhaustive match: `false` not covered")]
+		);
+	}
+}
@@ -1,90 +1,939 @@
+use std::{fmt, mem};
+
 use crate::{
-	ast,
-	lexer::{Lexer, Token, TokenKind},
+	ast::{Association, MatchArm, Node, NodeKind, Pattern, PatternKind},
+	lexer::{LexError, Lexer, Span, Token, TokenKind},
+	token_stream::TokenStream,
 };
 
+// prefix operators bind as tightly as `!` and `~`
+const PREFIX_PRECEDENCE: u8 = 12;
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+	pub(crate) kind: ParseErrorKind,
+	pub(crate) span: Span,
+	// where the parser picked up again after skipping the broken statement
+	pub(crate) recovery: Option<Span>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseErrorKind {
+	Expected { expected: String, found: String },
+	Lex(LexError),
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+impl fmt::Display for ParseErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Expected { expected, found } => {
+				write!(f, "expected {}, found {}", expected, found)
+			}
+			Self::Lex(err) => write!(f, "{}", err),
+		}
+	}
+}
+
+impl fmt::Display for ParseError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		// a string's span runs on past the line it starts on
+		let at = match self.kind {
+			ParseErrorKind::Lex(LexError::UnterminatedString(_)) => {
+				"starting at"
+			}
+			_ => "at",
+		};
+
+		write!(
+			f,
+			"{} {} {}:{}",
+			self.kind, at, self.span.line, self.span.col
+		)
+	}
+}
+
 pub struct Parser<'a> {
-	tokens: Lexer<'a>,
-	pub op_stack: Vec<Token>,
+	tokens: TokenStream<'a>,
+	last_span: Span,
+	errors: Vec<ParseError>,
+	// the lines of `///` comments consumed since the last statement started
+	docs: Vec<String>,
+	// whether a lexical error was skipped since the last token consumed, in
+	// which case the grammar error that follows is just its consequence
+	after_lex_error: bool,
+	// how many of the `{` consumed so far are still open
+	braces: usize,
 }
 
 impl<'a> Parser<'a> {
 	pub fn new(tokens: Lexer<'a>) -> Parser<'a> {
 		Self {
-			tokens,
-			op_stack: Vec::new(),
+			tokens: TokenStream::new(tokens),
+			last_span: Span::new(0, 0, 1, 0),
+			errors: Vec::new(),
+			docs: Vec::new(),
+			after_lex_error: false,
+			braces: 0,
 		}
 	}
 
-	pub fn parse(&mut self) -> Vec<Token> {
-		let mut out_stack = Vec::new();
+	pub fn parse(&mut self) -> Result<Vec<Node>, Vec<ParseError>> {
+		let mut stmts = Vec::new();
 
-		for token in self.tokens {
-			if !token.is_op() {
-				out_stack.push(token);
-			} else if token.kind != TokenKind::LParen
-				&& token.kind != TokenKind::RParen
-			{
-				// while op stack is not empty AND (op on top of the stack has
-				// greater precedence than current token OR (the operator on the
-				// top of the op stack has greater precedence than the current
-				// token OR (the operator on the top of the stack has an equal
-				// precedence to the current token AND the current token is left
-				// associative))) AND the top of the op stack is not a left
-				// parentheses, DO pop operators from the operator stack onto
-				// the output stack
-				while !self.op_stack.is_empty()
-					&& (self.op_stack.last().map(|op| op.get_precedence())
-						> Some(token.get_precedence())
-						|| (self.op_stack.last().map(|op| op.get_precedence())
-							== Some(token.get_precedence())
-							&& token.get_associativity()
-								== ast::Association::LTR))
-					&& !matches!(
-						self.op_stack.last(),
-						Some(&Token {
-							kind: TokenKind::LParen,
-							..
-						})
-					) {
-					out_stack.push(self.op_stack.pop().unwrap())
-				}
-				self.op_stack.push(token);
-			} else if token.kind == TokenKind::LParen {
-				self.op_stack.push(token);
-			} else if token.kind == TokenKind::RParen {
-				while !matches!(
-					self.op_stack.last(),
-					Some(&Token {
-						kind: TokenKind::LParen,
-						..
-					})
-				) {
-					if let Some(token) = self.op_stack.pop() {
-						out_stack.push(token);
-					} else {
-						break;
+		self.skip_delimeters();
+		while self.peek().is_some() {
+			let braces = self.braces;
+			match self.statement() {
+				Ok(stmt) => stmts.push(stmt),
+				Err(err) => self.recover(err, braces, false),
+			}
+			self.skip_delimeters();
+		}
+
+		if self.errors.is_empty() {
+			Ok(stmts)
+		} else {
+			Err(self.errors.drain(..).collect())
+		}
+	}
+
+	// Skips the rest of the broken statement, so the next one can be parsed
+	// on its own. `braces` is how many `{` were open when the statement
+	// started, so any it opened itself, like those of a `match` or a map,
+	// are skipped up to their `}` first.
+	fn recover(&mut self, mut err: ParseError, braces: usize, in_block: bool) {
+		// a lexical error was already reported where the grammar broke down
+		let reported = self.after_lex_error;
+		let mut depth = self.braces.saturating_sub(braces);
+
+		while let Some(kind) = self.peek_kind() {
+			match kind {
+				TokenKind::Delimeter if depth == 0 => {
+					self.advance();
+					break;
+				}
+				TokenKind::LBrace => depth += 1,
+				TokenKind::RBrace if depth > 0 => depth -= 1,
+				TokenKind::RBrace if in_block => break,
+				_ => {}
+			}
+			self.advance();
+		}
+
+		// doc comments inside the broken statement document nothing
+		self.docs.clear();
+		err.recovery = self.peek().map(|token| token.span);
+		if !reported {
+			self.errors.push(err);
+		}
+	}
+
+	fn error(
+		&self,
+		expected: impl Into<String>,
+		found: Option<&Token>,
+	) -> ParseError {
+		ParseError {
+			kind: ParseErrorKind::Expected {
+				expected: expected.into(),
+				found: found.map_or("end of input".into(), |token| {
+					token.kind.to_string()
+				}),
+			},
+			span: found.map_or(self.last_span, |token| token.span),
+			recovery: None,
+		}
+	}
+
+	// nodes span from their first token up to the last one consumed
+	fn node(&self, kind: NodeKind, start: Span) -> Node {
+		Node::new(kind, start.to(self.last_span))
+	}
+
+	// lexical errors are reported on the spot, so they never reach the
+	// grammar
+	fn peek(&mut self) -> Option<&Token> {
+		while let Some(Token {
+			kind: TokenKind::Error(err),
+			span,
+		}) = self.tokens.peek()
+		{
+			self.errors.push(ParseError {
+				kind: ParseErrorKind::Lex(err.clone()),
+				span: *span,
+				recovery: None,
+			});
+			self.after_lex_error = true;
+			self.last_span = self.tokens.next().unwrap().span;
+		}
+
+		self.tokens.peek()
+	}
+
+	fn peek_kind(&mut self) -> Option<&TokenKind> {
+		self.peek().map(|token| &token.kind)
+	}
+
+	// the kind of the token `n` places after the next one, looking past
+	// delimeters without consuming anything
+	fn lookahead(&self, n: usize) -> Option<TokenKind> {
+		self.tokens
+			.clone()
+			.map(|token| token.kind)
+			.filter(|kind| *kind != TokenKind::Delimeter)
+			.nth(n)
+	}
+
+	// in expression position, `{` starts a map when it is followed by `}` or
+	// by a key and a `:`, and a block otherwise
+	fn at_map(&self) -> bool {
+		match self.lookahead(1) {
+			Some(TokenKind::RBrace) => true,
+			Some(TokenKind::Ident(_)) | Some(TokenKind::String(_)) => {
+				self.lookahead(2) == Some(TokenKind::Colon)
+			}
+			_ => false,
+		}
+	}
+
+	// doc comments are trivia, set aside as the tokens after them are
+	// consumed for the statement they come before
+	fn advance(&mut self) -> Option<Token> {
+		self.peek();
+		self.docs
+			.extend(self.tokens.trivia().iter().filter_map(|token| {
+				match &token.kind {
+					TokenKind::DocComment(doc) => Some(doc.clone()),
+					_ => None,
+				}
+			}));
+		let token = self.tokens.next()?;
+		self.last_span = token.span;
+		self.after_lex_error = false;
+		match token.kind {
+			TokenKind::LBrace => self.braces += 1,
+			TokenKind::RBrace => self.braces = self.braces.saturating_sub(1),
+			_ => {}
+		}
+
+		Some(token)
+	}
+
+	// mismatched tokens are left in place, so recovery starts from them
+	fn expect(&mut self, kind: TokenKind) -> ParseResult<Token> {
+		match self.peek() {
+			Some(token) if token.kind == kind => Ok(self.advance().unwrap()),
+			token => {
+				let token = token.cloned();
+				Err(self.error(kind.to_string(), token.as_ref()))
+			}
+		}
+	}
+
+	fn expect_ident(&mut self) -> ParseResult<String> {
+		match self.peek() {
+			Some(Token {
+				kind: TokenKind::Ident(name),
+				..
+			}) => {
+				let name = name.clone();
+				self.advance();
+				Ok(name)
+			}
+			token => {
+				let token = token.cloned();
+				Err(self.error("identifier", token.as_ref()))
+			}
+		}
+	}
+
+	fn skip_delimeters(&mut self) {
+		while self.peek_kind() == Some(&TokenKind::Delimeter) {
+			self.advance();
+		}
+	}
+
+	// the doc comment right before a statement, with its lines joined
+	fn doc_comment(&mut self) -> Option<String> {
+		self.peek();
+		if self.docs.is_empty() {
+			None
+		} else {
+			Some(mem::take(&mut self.docs).join("\n"))
+		}
+	}
+
+	fn statement(&mut self) -> ParseResult<Node> {
+		let doc = self.doc_comment();
+		let stmt = match self.peek_kind() {
+			Some(TokenKind::Let) => self.let_statement(doc)?,
+			Some(TokenKind::Fun) => {
+				let fun = self.function(true, doc)?;
+
+				// anonymous functions are plain expressions, and may be called
+				// or combined right away
+				match fun.kind {
+					NodeKind::Fun { name: None, .. } => {
+						self.operators(fun, 0)?
 					}
+					_ => fun,
+				}
+			}
+			Some(TokenKind::Return) => self.return_statement()?,
+			Some(TokenKind::While) | Some(TokenKind::For) => {
+				let span = self.peek().unwrap().span;
+				self.loop_statement(None, span)?
+			}
+			Some(TokenKind::Label(_)) => {
+				let token = self.advance().unwrap();
+				let label = match token.kind {
+					TokenKind::Label(label) => label,
+					_ => unreachable!(),
+				};
+				self.expect(TokenKind::Colon)?;
+				self.loop_statement(Some(label), token.span)?
+			}
+			Some(TokenKind::Break) | Some(TokenKind::Continue) => {
+				self.jump_statement()?
+			}
+			Some(TokenKind::Import) => self.import_statement()?,
+			_ => self.expression(0)?,
+		};
+
+		// every statement ends at a delimeter, at the end of its block or at
+		// the end of the input
+		match self.peek_kind() {
+			None | Some(TokenKind::RBrace) => {}
+			Some(TokenKind::Delimeter) => {
+				self.advance();
+			}
+			_ => {
+				let token = self.peek().cloned();
+				return Err(self.error("end of statement", token.as_ref()));
+			}
+		}
+		// doc comments inside the statement, or after it on the same line,
+		// document nothing
+		self.docs.clear();
+
+		Ok(stmt)
+	}
+
+	fn let_statement(&mut self, doc: Option<String>) -> ParseResult<Node> {
+		let span = self.expect(TokenKind::Let)?.span;
+		let name = self.expect_ident()?;
+		self.expect(TokenKind::Assign)?;
+		let value = self.expression(0)?;
+
+		Ok(self.node(
+			NodeKind::Let {
+				name,
+				value: Box::new(value),
+				doc,
+			},
+			span,
+		))
+	}
+
+	// only statements may name a function, which declares it in the current
+	// scope
+	fn function(
+		&mut self,
+		named: bool,
+		doc: Option<String>,
+	) -> ParseResult<Node> {
+		let span = self.expect(TokenKind::Fun)?.span;
+		let name = match self.peek_kind() {
+			Some(TokenKind::Ident(_)) if named => Some(self.expect_ident()?),
+			_ => None,
+		};
+
+		self.expect(TokenKind::LParen)?;
+		let mut params = Vec::new();
+		while self.peek_kind() != Some(&TokenKind::RParen) {
+			params.push(self.expect_ident()?);
+			if self.peek_kind() != Some(&TokenKind::Comma) {
+				break;
+			}
+			self.advance();
+		}
+		self.expect(TokenKind::RParen)?;
+
+		let body = self.block()?;
+		let doc = doc.filter(|_| name.is_some());
+
+		Ok(self.node(
+			NodeKind::Fun {
+				name,
+				params,
+				body: Box::new(body),
+				doc,
+			},
+			span,
+		))
+	}
+
+	fn return_statement(&mut self) -> ParseResult<Node> {
+		let span = self.expect(TokenKind::Return)?.span;
+		let value = match self.peek_kind() {
+			None | Some(TokenKind::Delimeter) | Some(TokenKind::RBrace) => None,
+			_ => Some(Box::new(self.expression(0)?)),
+		};
+
+		Ok(self.node(NodeKind::Return(value), span))
+	}
+
+	fn if_expression(&mut self) -> ParseResult<Node> {
+		let span = self.expect(TokenKind::If)?.span;
+		let cond = self.expression(0)?;
+		let body = self.block()?;
+
+		let else_body = match self.peek_kind() {
+			Some(TokenKind::Else) => {
+				self.advance();
+				Some(Box::new(match self.peek_kind() {
+					Some(TokenKind::If) => self.if_expression()?,
+					_ => self.block()?,
+				}))
+			}
+			_ => None,
+		};
+
+		Ok(self.node(
+			NodeKind::If {
+				cond: Box::new(cond),
+				body: Box::new(body),
+				else_body,
+			},
+			span,
+		))
+	}
+
+	fn loop_statement(
+		&mut self,
+		label: Option<String>,
+		span: Span,
+	) -> ParseResult<Node> {
+		let kind = match self.peek_kind() {
+			Some(TokenKind::While) => {
+				self.advance();
+				let cond = self.expression(0)?;
+				let body = self.block()?;
+
+				NodeKind::While {
+					label,
+					cond: Box::new(cond),
+					body: Box::new(body),
+				}
+			}
+			Some(TokenKind::For) => {
+				self.advance();
+				let name = self.expect_ident()?;
+				self.expect(TokenKind::In)?;
+				let iterable = self.expression(0)?;
+				let body = self.block()?;
+
+				NodeKind::For {
+					label,
+					name,
+					iterable: Box::new(iterable),
+					body: Box::new(body),
 				}
-				if matches!(
-					self.op_stack.last(),
-					Some(&Token {
-						kind: TokenKind::LParen,
+			}
+			_ => {
+				let token = self.peek().cloned();
+				return Err(self.error("`while` or `for`", token.as_ref()));
+			}
+		};
+
+		Ok(self.node(kind, span))
+	}
+
+	fn jump_statement(&mut self) -> ParseResult<Node> {
+		let token = self.advance().unwrap();
+		let label = match self.peek_kind() {
+			Some(TokenKind::Label(label)) => {
+				let label = label.clone();
+				self.advance();
+				Some(label)
+			}
+			_ => None,
+		};
+
+		let kind = match token.kind {
+			TokenKind::Break => NodeKind::Break(label),
+			_ => NodeKind::Continue(label),
+		};
+
+		Ok(self.node(kind, token.span))
+	}
+
+	fn import_statement(&mut self) -> ParseResult<Node> {
+		let span = self.expect(TokenKind::Import)?.span;
+
+		self.expect(TokenKind::LBrace)?;
+		let mut names = Vec::new();
+		while self.peek_kind() != Some(&TokenKind::RBrace) {
+			names.push(self.expect_ident()?);
+			if self.peek_kind() != Some(&TokenKind::Comma) {
+				break;
+			}
+			self.advance();
+		}
+		self.expect(TokenKind::RBrace)?;
+
+		let path = match self.peek_kind() {
+			Some(TokenKind::From) => {
+				self.advance();
+				match self.peek() {
+					Some(Token {
+						kind: TokenKind::String(path),
 						..
-					})
-				) {
-					self.op_stack.pop();
+					}) => {
+						let path = path.clone();
+						self.advance();
+						Some(path)
+					}
+					token => {
+						let token = token.cloned();
+						return Err(self.error("module path", token.as_ref()));
+					}
+				}
+			}
+			_ => None,
+		};
+
+		Ok(self.node(NodeKind::Import { names, path }, span))
+	}
+
+	fn block(&mut self) -> ParseResult<Node> {
+		let span = self.expect(TokenKind::LBrace)?.span;
+		let mut stmts = Vec::new();
+
+		self.skip_delimeters();
+		while !matches!(self.peek_kind(), None | Some(TokenKind::RBrace)) {
+			let braces = self.braces;
+			match self.statement() {
+				Ok(stmt) => stmts.push(stmt),
+				Err(err) => self.recover(err, braces, true),
+			}
+			self.skip_delimeters();
+		}
+		self.expect(TokenKind::RBrace)?;
+
+		Ok(self.node(NodeKind::Block(stmts), span))
+	}
+
+	fn match_expression(&mut self) -> ParseResult<Node> {
+		let span = self.expect(TokenKind::Match)?.span;
+		let subject = self.expression(0)?;
+
+		self.expect(TokenKind::LBrace)?;
+		let mut arms = Vec::new();
+
+		self.skip_delimeters();
+		while self.peek_kind() != Some(&TokenKind::RBrace) {
+			let mut patterns = vec![self.pattern()?];
+			while self.peek_kind() == Some(&TokenKind::BitOr) {
+				self.advance();
+				patterns.push(self.pattern()?);
+			}
+
+			// a binding in one of several alternatives would be left unbound
+			// whenever another one matches
+			if patterns.len() > 1 {
+				if let Some(binding) = patterns.iter().find(|pattern| {
+					matches!(pattern.kind, PatternKind::Binding(_))
+				}) {
+					return Err(ParseError {
+						kind: ParseErrorKind::Expected {
+							expected: "literal or `_` pattern".into(),
+							found: "binding".into(),
+						},
+						span: binding.span,
+						recovery: None,
+					});
+				}
+			}
+
+			let guard = match self.peek_kind() {
+				Some(TokenKind::If) => {
+					self.advance();
+					Some(self.expression(0)?)
+				}
+				_ => None,
+			};
+			self.expect(TokenKind::MatchArm)?;
+			let body = self.expression(0)?;
+			arms.push(MatchArm {
+				patterns,
+				guard,
+				body,
+			});
+
+			if self.peek_kind() == Some(&TokenKind::Comma) {
+				self.advance();
+			}
+			self.skip_delimeters();
+		}
+		self.expect(TokenKind::RBrace)?;
+
+		Ok(self.node(
+			NodeKind::Match {
+				subject: Box::new(subject),
+				arms,
+			},
+			span,
+		))
+	}
+
+	fn list(&mut self) -> ParseResult<Node> {
+		let span = self.expect(TokenKind::LBracket)?.span;
+
+		let mut items = Vec::new();
+		while self.peek_kind() != Some(&TokenKind::RBracket) {
+			items.push(self.expression(0)?);
+			if self.peek_kind() != Some(&TokenKind::Comma) {
+				break;
+			}
+			self.advance();
+		}
+		self.expect(TokenKind::RBracket)?;
+
+		Ok(self.node(NodeKind::List(items), span))
+	}
+
+	// entries may span several lines
+	fn map(&mut self) -> ParseResult<Node> {
+		let span = self.expect(TokenKind::LBrace)?.span;
+
+		let mut entries = Vec::new();
+		self.skip_delimeters();
+		while self.peek_kind() != Some(&TokenKind::RBrace) {
+			let key = match self.peek_kind() {
+				Some(TokenKind::String(key)) => {
+					let key = key.clone();
+					self.advance();
+					key
+				}
+				_ => self.expect_ident()?,
+			};
+			self.expect(TokenKind::Colon)?;
+			entries.push((key, self.expression(0)?));
+
+			self.skip_delimeters();
+			if self.peek_kind() != Some(&TokenKind::Comma) {
+				break;
+			}
+			self.advance();
+			self.skip_delimeters();
+		}
+		self.expect(TokenKind::RBrace)?;
+
+		Ok(self.node(NodeKind::Map(entries), span))
+	}
+
+	fn pattern(&mut self) -> ParseResult<Pattern> {
+		let token =
+			match self.peek() {
+				Some(token)
+					if matches!(
+						token.kind,
+						TokenKind::Int(_)
+							| TokenKind::Float(_) | TokenKind::String(_)
+							| TokenKind::Ident(_) | TokenKind::True
+							| TokenKind::False | TokenKind::Minus
+					) =>
+				{
+					self.advance().unwrap()
+				}
+				token => {
+					let token = token.cloned();
+					return Err(self.error("pattern", token.as_ref()));
+				}
+			};
+
+		let kind = match token.kind {
+			TokenKind::Int(num) => PatternKind::Int(num),
+			TokenKind::Float(num) => PatternKind::Float(num),
+			TokenKind::String(str) => PatternKind::String(str),
+			TokenKind::True => PatternKind::Boolean(true),
+			TokenKind::False => PatternKind::Boolean(false),
+			TokenKind::Ident(name) if name == "_" => PatternKind::Wildcard,
+			TokenKind::Ident(name) => PatternKind::Binding(name),
+			_ => match self.peek() {
+				Some(&Token {
+					kind: TokenKind::Int(num),
+					..
+				}) => {
+					self.advance();
+					PatternKind::Int(-num)
+				}
+				Some(&Token {
+					kind: TokenKind::Float(num),
+					..
+				}) => {
+					self.advance();
+					PatternKind::Float(-num)
+				}
+				token => {
+					let token = token.cloned();
+					return Err(self.error("number", token.as_ref()));
+				}
+			},
+		};
+
+		Ok(Pattern {
+			kind,
+			span: token.span.to(self.last_span),
+		})
+	}
+
+	fn expression(&mut self, min_precedence: u8) -> ParseResult<Node> {
+		let lhs = self.prefix()?;
+		self.operators(lhs, min_precedence)
+	}
+
+	// applies every infix and postfix operator that binds tighter than
+	// `min_precedence` to an already parsed operand
+	fn operators(
+		&mut self,
+		mut lhs: Node,
+		min_precedence: u8,
+	) -> ParseResult<Node> {
+		loop {
+			let op = match self.peek() {
+				Some(op)
+					if (op.is_bin_op() || op.is_postfix_op())
+						&& op.get_precedence() > min_precedence =>
+				{
+					op.clone()
+				}
+				_ => break,
+			};
+
+			lhs = if op.is_postfix_op() {
+				self.postfix(lhs)?
+			} else {
+				self.infix(lhs)?
+			};
+		}
+
+		Ok(lhs)
+	}
+
+	fn prefix(&mut self) -> ParseResult<Node> {
+		match self.peek_kind() {
+			Some(TokenKind::Match) => return self.match_expression(),
+			Some(TokenKind::If) => return self.if_expression(),
+			Some(TokenKind::LBracket) => return self.list(),
+			Some(TokenKind::LBrace) => {
+				return if self.at_map() {
+					self.map()
 				} else {
-					panic!("Unmatched right parentheses: {:#?}", token);
+					self.block()
 				}
 			}
+			Some(TokenKind::Fun) => return self.function(false, None),
+			Some(TokenKind::TemplateStart(_)) => return self.interpolation(),
+			_ => {}
 		}
 
-		while !self.op_stack.is_empty() {
-			out_stack.push(self.op_stack.pop().unwrap());
+		let token = match self.peek() {
+			Some(token)
+				if token.is_prefix_op()
+					|| matches!(
+						token.kind,
+						TokenKind::Int(_)
+							| TokenKind::Float(_) | TokenKind::String(_)
+							| TokenKind::Ident(_) | TokenKind::True
+							| TokenKind::False | TokenKind::LParen
+					) =>
+			{
+				token.clone()
+			}
+			token => {
+				let token = token.cloned();
+				return Err(self.error("expression", token.as_ref()));
+			}
+		};
+		let span = token.span;
+		self.advance();
+
+		let kind = match token.kind {
+			TokenKind::Int(num) => NodeKind::IntLiteral(num),
+			TokenKind::Float(num) => NodeKind::FloatLiteral(num),
+			TokenKind::String(ref str) => NodeKind::StringLiteral(str.clone()),
+			TokenKind::Ident(ref ident) => NodeKind::Ident(ident.clone()),
+			TokenKind::True => NodeKind::BooleanLiteral(true),
+			TokenKind::False => NodeKind::BooleanLiteral(false),
+			TokenKind::LParen => {
+				let expr = self.expression(0)?;
+				self.expect(TokenKind::RParen)?;
+				return Ok(expr);
+			}
+			_ => {
+				let rhs = self.expression(PREFIX_PRECEDENCE)?;
+				NodeKind::UnaryExpr {
+					op: token,
+					rhs: Box::new(rhs),
+				}
+			}
+		};
+
+		Ok(self.node(kind, span))
+	}
+
+	// `"a ${b} c"` arrives as the start of the string, the tokens of `b` and
+	// the end of the string
+	fn interpolation(&mut self) -> ParseResult<Node> {
+		let mut token = self.advance().unwrap();
+		let span = token.span;
+		let mut parts = Vec::new();
+
+		loop {
+			let (text, end) = match token.kind {
+				TokenKind::TemplateStart(text)
+				| TokenKind::TemplateMiddle(text) => (text, false),
+				TokenKind::TemplateEnd(text) => (text, true),
+				_ => unreachable!(),
+			};
+			if !text.is_empty() {
+				parts
+					.push(Node::new(NodeKind::StringLiteral(text), token.span));
+			}
+			if end {
+				break;
+			}
+
+			parts.push(self.expression(0)?);
+			token = match self.peek() {
+				Some(Token {
+					kind:
+						TokenKind::TemplateMiddle(_) | TokenKind::TemplateEnd(_),
+					..
+				}) => self.advance().unwrap(),
+				token => {
+					let token = token.cloned();
+					return Err(self.error("`}`", token.as_ref()));
+				}
+			};
 		}
 
-		out_stack
+		Ok(self.node(NodeKind::Interpolation(parts), span))
+	}
+
+	fn infix(&mut self, lhs: Node) -> ParseResult<Node> {
+		let op = self.advance().unwrap();
+		let span = lhs.span;
+
+		// right-associative operators bind the rest of the expression at
+		// their own level
+		let precedence = match op.get_associativity() {
+			Association::Rtl => op.get_precedence() - 1,
+			_ => op.get_precedence(),
+		};
+		let rhs = self.expression(precedence)?;
+
+		let kind = match op.kind {
+			TokenKind::Assign => match lhs.kind {
+				NodeKind::Ident(name) => NodeKind::Assign {
+					name,
+					value: Box::new(rhs),
+				},
+				NodeKind::Index { object, index } => NodeKind::SetIndex {
+					object,
+					index,
+					value: Box::new(rhs),
+				},
+				NodeKind::Member { object, field } => NodeKind::SetMember {
+					object,
+					field,
+					value: Box::new(rhs),
+				},
+				_ => {
+					return Err(ParseError {
+						kind: ParseErrorKind::Expected {
+							expected: "variable, index or field before `=`"
+								.into(),
+							found: "expression".into(),
+						},
+						span: op.span,
+						recovery: None,
+					})
+				}
+			},
+			_ => NodeKind::BinExpr {
+				op,
+				lhs: Box::new(lhs),
+				rhs: Box::new(rhs),
+			},
+		};
+
+		Ok(self.node(kind, span))
+	}
+
+	fn postfix(&mut self, lhs: Node) -> ParseResult<Node> {
+		let op = self.advance().unwrap();
+		let span = lhs.span;
+
+		let kind = match op.kind {
+			TokenKind::Period => NodeKind::Member {
+				object: Box::new(lhs),
+				field: self.expect_ident()?,
+			},
+			TokenKind::LParen => {
+				let mut args = Vec::new();
+				while self.peek_kind() != Some(&TokenKind::RParen) {
+					args.push(self.expression(0)?);
+					if self.peek_kind() != Some(&TokenKind::Comma) {
+						break;
+					}
+					self.advance();
+				}
+				self.expect(TokenKind::RParen)?;
+
+				NodeKind::Call {
+					callee: Box::new(lhs),
+					args,
+				}
+			}
+			TokenKind::LBracket => {
+				let start = match self.peek_kind() {
+					Some(TokenKind::Colon) => None,
+					_ => Some(Box::new(self.expression(0)?)),
+				};
+
+				match (start, self.peek_kind() == Some(&TokenKind::Colon)) {
+					(Some(index), false) => {
+						self.expect(TokenKind::RBracket)?;
+						NodeKind::Index {
+							object: Box::new(lhs),
+							index,
+						}
+					}
+					(start, _) => {
+						self.expect(TokenKind::Colon)?;
+						let end = match self.peek_kind() {
+							Some(TokenKind::RBracket) => None,
+							_ => Some(Box::new(self.expression(0)?)),
+						};
+						self.expect(TokenKind::RBracket)?;
+
+						NodeKind::Slice {
+							object: Box::new(lhs),
+							start,
+							end,
+						}
+					}
+				}
+			}
+			_ => unreachable!(),
+		};
+
+		Ok(self.node(kind, span))
 	}
 }
 
@@ -92,37 +941,401 @@ impl<'a> Parser<'a> {
 mod test {
 	use super::*;
 
-	use crate::lexer::{Lexer, Token, TokenKind::*};
+	use crate::lexer::{Lexer, Span, Token, TokenKind::*};
 
-	#[test]
-	fn gen_postfix_stack() {
-		let lexer = Lexer::new("1 + 2 * 3");
-		let stack = Parser::new(lexer).parse();
+	fn parse(input: &str) -> Vec<Node> {
+		Parser::new(Lexer::new(input)).parse().unwrap()
+	}
 
+	fn parse_errors(input: &str) -> Vec<ParseError> {
+		Parser::new(Lexer::new(input)).parse().unwrap_err()
+	}
+
+	#[test]
+	fn parse_precedence() {
 		assert_eq!(
-			stack,
-			vec![
-				Token {
-					kind: Number(1.0),
-					span: (1, 0),
+			parse("1 + 2 * 3"),
+			vec![Node::new(
+				NodeKind::BinExpr {
+					op: Token {
+						kind: Plus,
+						span: Span::new(2, 3, 1, 2),
+					},
+					lhs: Box::new(Node::new(
+						NodeKind::IntLiteral(1),
+						Span::new(0, 1, 1, 0)
+					)),
+					rhs: Box::new(Node::new(
+						NodeKind::BinExpr {
+							op: Token {
+								kind: Multiply,
+								span: Span::new(6, 7, 1, 6),
+							},
+							lhs: Box::new(Node::new(
+								NodeKind::IntLiteral(2),
+								Span::new(4, 5, 1, 4)
+							)),
+							rhs: Box::new(Node::new(
+								NodeKind::IntLiteral(3),
+								Span::new(8, 9, 1, 8)
+							)),
+						},
+						Span::new(4, 9, 1, 4)
+					)),
 				},
-				Token {
-					kind: Number(2.0),
-					span: (1, 4),
-				},
-				Token {
-					kind: Number(3.0),
-					span: (1, 8),
+				Span::new(0, 9, 1, 0)
+			)]
+		)
+	}
+
+	#[test]
+	fn parse_left_associativity() {
+		let tree = parse("1 - 2 - 3");
+
+		match &tree[0].kind {
+			NodeKind::BinExpr { lhs, rhs, .. } => {
+				assert!(matches!(lhs.kind, NodeKind::BinExpr { .. }));
+				assert_eq!(rhs.kind, NodeKind::IntLiteral(3));
+			}
+			node => panic!("Expected binary expression, found: {:#?}", node),
+		}
+	}
+
+	#[test]
+	fn parse_right_associativity() {
+		let tree = parse("a = b = !c");
+
+		match &tree[0].kind {
+			NodeKind::Assign { name, value } => {
+				assert_eq!(name, "a");
+				assert!(matches!(
+					value.kind,
+					NodeKind::Assign { ref name, .. } if name == "b"
+				));
+			}
+			node => panic!("Expected assignment, found: {:#?}", node),
+		}
+	}
+
+	#[test]
+	fn parse_member_call() {
+		assert_eq!(
+			parse("std.print(c)"),
+			vec![Node::new(
+				NodeKind::Call {
+					callee: Box::new(Node::new(
+						NodeKind::Member {
+							object: Box::new(Node::new(
+								NodeKind::Ident("std".into()),
+								Span::new(0, 3, 1, 0)
+							)),
+							field: "print".into(),
+						},
+						Span::new(0, 9, 1, 0)
+					)),
+					args: vec![Node::new(
+						NodeKind::Ident("c".into()),
+						Span::new(10, 11, 1, 10)
+					)],
 				},
-				Token {
-					kind: Multiply,
-					span: (1, 6),
+				Span::new(0, 12, 1, 0)
+			)]
+		)
+	}
+
+	#[test]
+	fn parse_example() {
+		let tree = parse(include_str!("../example.dt"));
+
+		assert!(matches!(
+			tree.iter().map(|node| &node.kind).collect::<Vec<_>>()[..],
+			[
+				NodeKind::Import { .. },
+				NodeKind::Fun { .. },
+				NodeKind::Let { .. },
+				NodeKind::Match { .. },
+				NodeKind::If { .. },
+			]
+		))
+	}
+
+	#[test]
+	fn parse_else_if() {
+		let tree = parse("let x = if a { 1 } else if b { 2 } else { 3 }");
+
+		match &tree[0].kind {
+			NodeKind::Let { value, .. } => match &value.kind {
+				NodeKind::If {
+					else_body: Some(else_body),
+					..
+				} => {
+					assert!(matches!(
+						else_body.kind,
+						NodeKind::If {
+							else_body: Some(_),
+							..
+						}
+					));
+					assert_eq!(else_body.span, Span::new(24, 45, 1, 24));
+				}
+				node => panic!("Expected if expression, found: {:#?}", node),
+			},
+			node => panic!("Expected let statement, found: {:#?}", node),
+		}
+	}
+
+	#[test]
+	fn parse_index_and_slice() {
+		let tree = parse("xs[i] = [ys[1:], ys[:-1]][0]");
+
+		match &tree[0].kind {
+			NodeKind::SetIndex { value, .. } => match &value.kind {
+				NodeKind::Index { object, .. } => match &object.kind {
+					NodeKind::List(items) => {
+						assert!(matches!(
+							items[0].kind,
+							NodeKind::Slice {
+								start: Some(_),
+								end: None,
+								..
+							}
+						));
+						assert!(matches!(
+							items[1].kind,
+							NodeKind::Slice {
+								start: None,
+								end: Some(_),
+								..
+							}
+						));
+					}
+					node => panic!("Expected list, found: {:#?}", node),
 				},
-				Token {
-					kind: Plus,
-					span: (1, 2),
+				node => panic!("Expected index, found: {:#?}", node),
+			},
+			node => panic!("Expected index assignment, found: {:#?}", node),
+		}
+	}
+
+	#[test]
+	fn parse_map_or_block() {
+		let tree = parse(
+			"{}\n{ a }\n{ a: 1 }\n{\n\t\"b c\": 2,\n\td: { e: 3 },\n}\nlet f \
+			 = { g }",
+		);
+
+		assert_eq!(
+			tree.iter()
+				.map(|node| match &node.kind {
+					NodeKind::Map(entries) => format!("map {}", entries.len()),
+					NodeKind::Block(_) => "block".into(),
+					NodeKind::Let { value, .. } => {
+						format!(
+							"let {:?}",
+							matches!(value.kind, NodeKind::Block(_))
+						)
+					}
+					node => panic!("Expected map or block, found: {:#?}", node),
+				})
+				.collect::<Vec<_>>(),
+			vec!["map 0", "block", "map 1", "map 2", "let true"]
+		);
+	}
+
+	#[test]
+	fn parse_doc_comments() {
+		let tree = parse(
+			"/// Adds one.\n///\n/// Returns an int.\nfun inc(n) {\n\t/// \
+			 step\n\tlet step = 1 /// ignored\n\tn + step\n}\n/// nothing \
+			 here\n1\nlet x = fun () {} /* not a doc */\n/// anonymous\nfun \
+			 () {}",
+		);
+
+		let doc = |node: &Node| match &node.kind {
+			NodeKind::Let { doc, .. } | NodeKind::Fun { doc, .. } => {
+				doc.clone()
+			}
+			_ => None,
+		};
+		let body = match &tree[0].kind {
+			NodeKind::Fun { body, .. } => match &body.kind {
+				NodeKind::Block(stmts) => stmts.clone(),
+				node => panic!("Expected block, found: {:#?}", node),
+			},
+			node => panic!("Expected function, found: {:#?}", node),
+		};
+
+		assert_eq!(
+			tree.iter().chain(&body).map(doc).collect::<Vec<_>>(),
+			vec![
+				Some("Adds one.\n\nReturns an int.".into()),
+				None,
+				None,
+				None,
+				Some("step".into()),
+				None,
+			]
+		);
+	}
+
+	#[test]
+	fn parse_interpolation() {
+		let tree = parse("\"a ${ b } c ${d}\"");
+
+		assert_eq!(
+			tree[0].kind,
+			NodeKind::Interpolation(vec![
+				Node::new(
+					NodeKind::StringLiteral("a ".into()),
+					Span::new(0, 5, 1, 0)
+				),
+				Node::new(NodeKind::Ident("b".into()), Span::new(6, 7, 1, 6)),
+				Node::new(
+					NodeKind::StringLiteral(" c ".into()),
+					Span::new(8, 14, 1, 8)
+				),
+				Node::new(
+					NodeKind::Ident("d".into()),
+					Span::new(14, 15, 1, 14)
+				),
+			])
+		);
+		assert_eq!(tree[0].span, Span::new(0, 17, 1, 0));
+		assert_eq!(
+			parse_errors("\"${}\"\n\"${a b}\"")
+				.iter()
+				.map(ToString::to_string)
+				.collect::<Vec<_>>(),
+			vec![
+				"expected expression, found `}` at 1:3",
+				"expected `}`, found identifier `b` at 2:5",
+			]
+		);
+	}
+
+	#[test]
+	fn parse_match_patterns() {
+		let tree =
+			parse("match x {\n\t-1 | \"a\" -> 1\n\tn if n > 2 -> n, _ -> 0\n}");
+
+		match &tree[0].kind {
+			NodeKind::Match { arms, .. } => {
+				assert_eq!(
+					arms.iter()
+						.map(|arm| arm
+							.patterns
+							.iter()
+							.map(|pattern| pattern.kind.clone())
+							.collect())
+						.collect::<Vec<Vec<_>>>(),
+					vec![
+						vec![
+							PatternKind::Int(-1),
+							PatternKind::String("a".into())
+						],
+						vec![PatternKind::Binding("n".into())],
+						vec![PatternKind::Wildcard],
+					]
+				);
+				assert_eq!(arms[0].patterns[0].span, Span::new(11, 13, 2, 1));
+				assert!(arms[1].guard.is_some());
+			}
+			node => panic!("Expected match expression, found: {:#?}", node),
+		}
+
+		assert_eq!(
+			parse_errors("match x { 1 | n -> n }")
+				.iter()
+				.map(ToString::to_string)
+				.collect::<Vec<_>>(),
+			vec!["expected literal or `_` pattern, found binding at 1:14"]
+		);
+	}
+
+	#[test]
+	fn parse_multiline_expressions() {
+		let tree = parse(
+			"let total = add(\n\t1, // one\n\t2,\n) +\n\t/* two */ \
+			 [\n\t\t3,\n\t][0] ||\n\tfalse\ntotal",
+		);
+
+		assert!(matches!(
+			tree.iter().map(|node| &node.kind).collect::<Vec<_>>()[..],
+			[
+				NodeKind::Let { value, .. },
+				NodeKind::Ident(_),
+			] if matches!(value.kind, NodeKind::BinExpr { .. })
+		))
+	}
+
+	#[test]
+	fn parse_unmatched_parens() {
+		assert_eq!(
+			parse_errors("(1 + 2\n1 + 2)\nlet a = 1"),
+			vec![ParseError {
+				kind: ParseErrorKind::Expected {
+					expected: "`)`".into(),
+					found: "integer `1`".into(),
 				},
+				span: Span::new(7, 8, 2, 0),
+				recovery: Some(Span::new(14, 17, 3, 0)),
+			}]
+		)
+	}
+
+	#[test]
+	fn parse_recovers_inside_blocks() {
+		let errors = parse_errors("fun f() {\n\tlet = 1\n\tf(\n}\n1 +");
+
+		assert_eq!(
+			errors
+				.iter()
+				.map(|err| (
+					(err.span.line, err.span.col),
+					err.kind.to_string()
+				))
+				.collect::<Vec<_>>(),
+			vec![
+				((2, 5), "expected identifier, found `=`".into()),
+				((4, 0), "expected expression, found `}`".into()),
+				((5, 2), "expected expression, found end of input".into())
+			]
+		)
+	}
+
+	#[test]
+	fn parse_recovers_inside_match_and_map() {
+		let errors = parse_errors(
+			"fun f(x) {\n\tmatch x {\n\t\t1 -> ,\n\t\t_ -> \
+			 2\n\t}\n\tx\n}\nlet y = {\n\ta: ,\n\tb: 1\n}\ny",
+		);
+
+		assert_eq!(
+			errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+			vec![
+				"expected expression, found `,` at 3:7",
+				"expected expression, found `,` at 9:4",
 			]
 		)
 	}
+
+	#[test]
+	fn parse_lex_errors() {
+		// each lexical error is reported once, without the grammar errors it
+		// leads to
+		let errors = parse_errors(
+			"let a = 1 @ 2\nlet b = 0x\nlet c = (1 +\n\t2 @)\nlet d = \"oops",
+		);
+
+		assert_eq!(
+			errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+			vec![
+				"unexpected character '@' at 1:10",
+				"malformed number literal `0x` at 2:8",
+				"unexpected character '@' at 4:3",
+				"unterminated string literal starting at 5:8",
+			]
+		);
+		assert_eq!(errors[3].kind.to_string(), "unterminated string literal");
+	}
 }
@@ -1,28 +1,83 @@
-use crate::{lexer::Lexer, parser::Parser};
+use crate::{
+	checker::Checker,
+	diagnostics::{self, Diagnostic},
+	eval::{Interpreter, NodeResult},
+	lexer::Lexer,
+	module::Source,
+	parser::Parser,
+	resolver::Resolver,
+};
 
 use std::{io, io::Write};
 
 const REPL_CHAR: &str = "◭ ";
 const REPL_VERSION: &str = "0.1.1";
+const REPL_FILE: &str = "<repl>";
 
-pub(crate) fn repl() -> io::Result<()> {
+pub(crate) fn repl(strict: bool) -> io::Result<()> {
 	println!("Delta v{} REPL", REPL_VERSION);
 	println!("Type `exit` to exit.");
 
+	let mut interpreter = Interpreter::new();
+	interpreter.set_strict(strict);
+
 	loop {
 		print!("{}", REPL_CHAR);
 		io::stdout().flush()?;
 
 		let mut input = String::new();
-		io::stdin().read_line(&mut input)?;
+		if io::stdin().read_line(&mut input)? == 0 {
+			break;
+		}
 
 		match input.trim() {
 			"exit" => break,
 			_ => {
-				let lexer = Lexer::new(input.trim());
-				let stack = Parser::new(lexer).parse();
+				let source = input.trim();
+				let lexer = Lexer::new(source);
+				let tree = match Parser::new(lexer).parse() {
+					Ok(tree) => tree,
+					Err(errors) => {
+						diagnostics::emit(
+							errors.iter().map(Diagnostic::from),
+							REPL_FILE,
+							source,
+						);
+						continue;
+					}
+				};
+
+				let globals = interpreter.global_names();
+				if let Err(errors) = Resolver::new(globals).resolve(&tree) {
+					diagnostics::emit(
+						errors.iter().map(Diagnostic::from),
+						REPL_FILE,
+						source,
+					);
+					continue;
+				}
+
+				let warnings = Checker::new().check(&tree);
+				diagnostics::emit(
+					warnings.iter().map(Diagnostic::from),
+					REPL_FILE,
+					source,
+				);
+				if strict && !warnings.is_empty() {
+					continue;
+				}
 
-				println!("{:#?}", stack);
+				// imports are looked up relative to the working directory
+				interpreter.set_source(Source::new(REPL_FILE, "", source));
+				let result = interpreter.run(&tree);
+				diagnostics::emit_sourced(&interpreter.take_warnings());
+				match result {
+					Ok(NodeResult::Nil) => {}
+					Ok(value) => println!("{}", value),
+					Err(err) => {
+						diagnostics::emit_runtime(&err, REPL_FILE, source)
+					}
+				}
 			}
 		}
 	}
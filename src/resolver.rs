@@ -0,0 +1,411 @@
+use std::{collections::HashSet, fmt, mem};
+
+use crate::{
+	ast::{Node, NodeKind, PatternKind},
+	lexer::Span,
+};
+
+#[derive(Debug, PartialEq)]
+pub struct ResolveError {
+	pub(crate) kind: ResolveErrorKind,
+	pub(crate) span: Span,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ResolveErrorKind {
+	Undeclared(String),
+	UndeclaredAssign(String),
+	ReturnOutsideFunction,
+	// `break` or `continue`
+	OutsideLoop(&'static str),
+	UndeclaredLabel(String),
+}
+
+impl fmt::Display for ResolveErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Undeclared(name) => {
+				write!(f, "cannot find variable `{}` in this scope", name)
+			}
+			Self::UndeclaredAssign(name) => {
+				write!(f, "cannot assign to undeclared variable `{}`", name)
+			}
+			Self::ReturnOutsideFunction => {
+				write!(f, "`return` outside of a function")
+			}
+			Self::OutsideLoop(keyword) => {
+				write!(f, "`{}` outside of a loop", keyword)
+			}
+			Self::UndeclaredLabel(label) => {
+				write!(f, "use of undeclared label `'{}`", label)
+			}
+		}
+	}
+}
+
+impl fmt::Display for ResolveError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} at {}:{}", self.kind, self.span.line, self.span.col)
+	}
+}
+
+// Walks the tree before it runs, tracking which names each scope declares, so
+// references to variables that can never exist are reported up front.
+pub struct Resolver<'a> {
+	scopes: Vec<HashSet<String>>,
+	// Function bodies only run once they are called, after the scope they are
+	// defined in may have declared more names, so each scope resolves its
+	// functions when it ends. This lets functions call ones declared after
+	// them.
+	functions_of: Vec<Vec<&'a Node>>,
+	// how many function bodies enclose the current node
+	functions: usize,
+	// the labels of the loops around the current node, innermost last
+	loops: Vec<Option<String>>,
+	errors: Vec<ResolveError>,
+}
+
+impl<'a> Resolver<'a> {
+	pub fn new(globals: impl IntoIterator<Item = String>) -> Resolver<'a> {
+		Self {
+			scopes: vec![globals.into_iter().collect()],
+			functions_of: vec![Vec::new()],
+			functions: 0,
+			loops: Vec::new(),
+			errors: Vec::new(),
+		}
+	}
+
+	pub fn resolve(
+		mut self,
+		stmts: &'a [Node],
+	) -> Result<(), Vec<ResolveError>> {
+		for stmt in stmts {
+			self.node(stmt);
+		}
+		self.end_scope();
+
+		if self.errors.is_empty() {
+			Ok(())
+		} else {
+			// function bodies are resolved out of order, so errors are put
+			// back in the order of their lines
+			self.errors.sort_by_key(|err| err.span.line);
+			Err(self.errors)
+		}
+	}
+
+	fn declare(&mut self, name: &str) {
+		self.scopes
+			.last_mut()
+			.expect("the global scope is never popped")
+			.insert(name.into());
+	}
+
+	fn is_declared(&self, name: &str) -> bool {
+		self.scopes.iter().any(|scope| scope.contains(name))
+	}
+
+	fn begin_scope(&mut self, names: &[String]) {
+		self.scopes.push(names.iter().cloned().collect());
+		self.functions_of.push(Vec::new());
+	}
+
+	// resolves the functions defined in the innermost scope, which can now
+	// see everything it declares, and then leaves it
+	fn end_scope(&mut self) {
+		let functions = self
+			.functions_of
+			.pop()
+			.expect("every scope has a list of functions");
+		for function in functions {
+			self.function(function);
+		}
+		self.scopes.pop();
+	}
+
+	fn scoped(&mut self, names: &[String], body: &'a Node) {
+		self.begin_scope(names);
+		self.node(body);
+		self.end_scope();
+	}
+
+	fn function(&mut self, node: &'a Node) {
+		if let NodeKind::Fun { params, body, .. } = &node.kind {
+			// loops outside the function can't be broken out of from inside
+			// it
+			let loops = mem::take(&mut self.loops);
+			self.functions += 1;
+			self.scoped(params, body);
+			self.functions -= 1;
+			self.loops = loops;
+		}
+	}
+
+	fn error(&mut self, kind: ResolveErrorKind, span: Span) {
+		self.errors.push(ResolveError { kind, span });
+	}
+
+	fn jump(
+		&mut self,
+		keyword: &'static str,
+		label: &Option<String>,
+		span: Span,
+	) {
+		if self.loops.is_empty() {
+			self.error(ResolveErrorKind::OutsideLoop(keyword), span);
+		} else if label.is_some() && !self.loops.contains(label) {
+			let label = label.clone().unwrap();
+			self.error(ResolveErrorKind::UndeclaredLabel(label), span);
+		}
+	}
+
+	fn node(&mut self, node: &'a Node) {
+		match &node.kind {
+			NodeKind::Ident(name) => {
+				if !self.is_declared(name) {
+					self.error(
+						ResolveErrorKind::Undeclared(name.clone()),
+						node.span,
+					);
+				}
+			}
+			NodeKind::Let { name, value, .. } => {
+				// the initializer is resolved first, so `let a = a` refers to
+				// an outer `a`
+				self.node(value);
+				self.declare(name);
+			}
+			NodeKind::Assign { name, value } => {
+				self.node(value);
+				if !self.is_declared(name) {
+					self.error(
+						ResolveErrorKind::UndeclaredAssign(name.clone()),
+						node.span,
+					);
+				}
+			}
+			NodeKind::Fun { name, .. } => {
+				if let Some(name) = name {
+					self.declare(name);
+				}
+				self.functions_of
+					.last_mut()
+					.expect("the global scope is never popped")
+					.push(node);
+			}
+			NodeKind::Import { names, .. } => {
+				for name in names {
+					self.declare(name);
+				}
+			}
+			NodeKind::Block(stmts) => {
+				self.begin_scope(&[]);
+				for stmt in stmts {
+					self.node(stmt);
+				}
+				self.end_scope();
+			}
+			NodeKind::BinExpr { lhs, rhs, .. } => {
+				self.node(lhs);
+				self.node(rhs);
+			}
+			NodeKind::UnaryExpr { rhs, .. } => self.node(rhs),
+			NodeKind::Call { callee, args } => {
+				self.node(callee);
+				for arg in args {
+					self.node(arg);
+				}
+			}
+			NodeKind::Member { object, .. } => self.node(object),
+			NodeKind::Index { object, index } => {
+				self.node(object);
+				self.node(index);
+			}
+			NodeKind::Slice { object, start, end } => {
+				self.node(object);
+				for bound in start.iter().chain(end) {
+					self.node(bound);
+				}
+			}
+			NodeKind::SetIndex {
+				object,
+				index,
+				value,
+			} => {
+				self.node(object);
+				self.node(index);
+				self.node(value);
+			}
+			NodeKind::SetMember { object, value, .. } => {
+				self.node(object);
+				self.node(value);
+			}
+			NodeKind::List(items) | NodeKind::Interpolation(items) => {
+				for item in items {
+					self.node(item);
+				}
+			}
+			NodeKind::Map(entries) => {
+				for (_, value) in entries {
+					self.node(value);
+				}
+			}
+			NodeKind::Match { subject, arms } => {
+				self.node(subject);
+				for arm in arms {
+					self.begin_scope(&[]);
+					for pattern in &arm.patterns {
+						if let PatternKind::Binding(name) = &pattern.kind {
+							self.declare(name);
+						}
+					}
+					if let Some(guard) = &arm.guard {
+						self.node(guard);
+					}
+					self.node(&arm.body);
+					self.end_scope();
+				}
+			}
+			NodeKind::If {
+				cond,
+				body,
+				else_body,
+			} => {
+				self.node(cond);
+				self.node(body);
+				if let Some(else_body) = else_body {
+					self.node(else_body);
+				}
+			}
+			NodeKind::Return(value) => {
+				if self.functions == 0 {
+					self.error(
+						ResolveErrorKind::ReturnOutsideFunction,
+						node.span,
+					);
+				}
+				if let Some(value) = value {
+					self.node(value);
+				}
+			}
+			NodeKind::While { label, cond, body } => {
+				self.node(cond);
+				self.loops.push(label.clone());
+				self.node(body);
+				self.loops.pop();
+			}
+			NodeKind::For {
+				label,
+				name,
+				iterable,
+				body,
+			} => {
+				self.node(iterable);
+				self.loops.push(label.clone());
+				self.scoped(std::slice::from_ref(name), body);
+				self.loops.pop();
+			}
+			NodeKind::Break(label) => self.jump("break", label, node.span),
+			NodeKind::Continue(label) => {
+				self.jump("continue", label, node.span)
+			}
+			NodeKind::IntLiteral(_)
+			| NodeKind::FloatLiteral(_)
+			| NodeKind::BooleanLiteral(_)
+			| NodeKind::StringLiteral(_) => {}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	use crate::{lexer::Lexer, parser::Parser};
+
+	fn resolve(input: &str) -> Result<(), Vec<String>> {
+		let tree = Parser::new(Lexer::new(input)).parse().unwrap();
+
+		Resolver::new(Vec::new())
+			.resolve(&tree)
+			.map_err(|errors| errors.iter().map(ToString::to_string).collect())
+	}
+
+	#[test]
+	fn resolve_example() {
+		assert_eq!(
+			resolve(include_str!("../example.dt")),
+			Err(vec![
+				"cannot find variable `c` in this scope at 16:12".into()
+			])
+		)
+	}
+
+	#[test]
+	fn resolve_outer_scopes() {
+		assert_eq!(
+			resolve("let a = 1\n{\n\tlet b = a\n\t{ a = b }\n}\nlet a = a + 1"),
+			Ok(())
+		)
+	}
+
+	#[test]
+	fn resolve_block_ends_scope() {
+		assert_eq!(
+			resolve("{ let a = 1 }\nb = a\nfun f(x) { x }\nx"),
+			Err(vec![
+				"cannot find variable `a` in this scope at 2:4".into(),
+				"cannot assign to undeclared variable `b` at 2:0".into(),
+				"cannot find variable `x` in this scope at 4:0".into(),
+			])
+		)
+	}
+
+	#[test]
+	fn resolve_loops() {
+		assert_eq!(
+			resolve(
+				"break\n'a: while true {\n\tfor x in 0..3 { continue 'a \
+				 }\n\tbreak 'b\n\tfun f() { break }\n}\nx"
+			),
+			Err(vec![
+				"`break` outside of a loop at 1:0".into(),
+				"use of undeclared label `'b` at 4:1".into(),
+				"`break` outside of a loop at 5:11".into(),
+				"cannot find variable `x` in this scope at 7:0".into(),
+			])
+		)
+	}
+
+	#[test]
+	fn resolve_match_bindings() {
+		assert_eq!(
+			resolve("match 1 { n if n > 0 -> n, _ -> 0 }\nn"),
+			Err(vec!["cannot find variable `n` in this scope at 2:0".into()])
+		)
+	}
+
+	#[test]
+	fn resolve_functions() {
+		assert_eq!(
+			resolve(
+				"let n = 1\nlet add = fun (x) { return x + n }\nfun f() { \
+				 return f }\nreturn add(1)"
+			),
+			Err(vec!["`return` outside of a function at 4:0".into()])
+		);
+
+		// bodies can use functions declared after them, but not names from
+		// scopes that have already ended
+		assert_eq!(
+			resolve(
+				"fun is_even(n) { if n == 0 { true } else { is_odd(n - 1) } \
+				 }\nfun is_odd(n) { if n == 0 { false } else { is_even(n - 1) \
+				 } }\n{\n\tfun f() { g() + h }\n\tfun g() { 1 }\n\tlet h = \
+				 2\n}\nfun k() { h }"
+			),
+			Err(vec!["cannot find variable `h` in this scope at 8:10".into()])
+		)
+	}
+}
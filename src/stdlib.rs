@@ -0,0 +1,488 @@
+use std::{
+	cell::{Cell, RefCell},
+	collections::BTreeMap,
+	convert::TryFrom,
+	io::{self, Write},
+	rc::Rc,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::eval::{Native, NativeFn, NodeResult};
+
+type Builtin = (&'static str, fn() -> Module);
+
+// every module `import { name }` brings in without a path
+const BUILTINS: &[Builtin] = &[("std", std)];
+
+pub(crate) fn builtin(name: &str) -> Option<NodeResult> {
+	BUILTINS
+		.iter()
+		.find(|(builtin, _)| *builtin == name)
+		.map(|(_, module)| module().build())
+}
+
+// A namespace of natives, and of other namespaces, that turns into a map when
+// it is imported. New natives only need a function with the `NativeFn`
+// signature and a line registering it.
+pub(crate) struct Module {
+	members: BTreeMap<String, NodeResult>,
+}
+
+impl Module {
+	pub(crate) fn new() -> Module {
+		Self {
+			members: BTreeMap::new(),
+		}
+	}
+
+	pub(crate) fn native(
+		mut self,
+		name: &'static str,
+		arity: usize,
+		fun: NativeFn,
+	) -> Module {
+		let native = Native { name, arity, fun };
+		self.members
+			.insert(name.into(), NodeResult::Native(Rc::new(native)));
+		self
+	}
+
+	pub(crate) fn module(mut self, name: &str, module: Module) -> Module {
+		self.members.insert(name.into(), module.build());
+		self
+	}
+
+	pub(crate) fn build(self) -> NodeResult {
+		NodeResult::Map(Rc::new(RefCell::new(self.members)))
+	}
+}
+
+fn std() -> Module {
+	Module::new()
+		.native("print", 1, print)
+		.native("println", 1, println)
+		.native("eprint", 1, eprint)
+		.native("len", 1, len)
+		.native("str", 1, str)
+		.native("num", 1, num)
+		.native("split", 2, split)
+		.native("join", 2, join)
+		.native("trim", 1, trim)
+		.native("upper", 1, upper)
+		.native("lower", 1, lower)
+		.native("contains", 2, contains)
+		.native("replace", 3, replace)
+		.native("push", 2, push)
+		.native("pop", 1, pop)
+		.native("keys", 1, keys)
+		.native("values", 1, values)
+		.native("sort", 1, sort)
+		.module("math", math())
+}
+
+fn math() -> Module {
+	Module::new()
+		.native("floor", 1, floor)
+		.native("sqrt", 1, sqrt)
+		.native("pow", 2, pow)
+		.native("min", 2, min)
+		.native("max", 2, max)
+		.native("random", 0, random)
+		.native("seed", 1, seed)
+}
+
+fn expected(what: &str, value: &NodeResult) -> String {
+	format!("expected {}, found {}", what, value.type_name())
+}
+
+// ints and floats, as a float
+fn number(value: &NodeResult) -> Result<f64, String> {
+	match value {
+		NodeResult::Int(num) => Ok(*num as f64),
+		NodeResult::Float(num) => Ok(*num),
+		_ => Err(expected("number", value)),
+	}
+}
+
+fn int(value: &NodeResult) -> Result<i64, String> {
+	match value {
+		NodeResult::Int(num) => Ok(*num),
+		_ => Err(expected("int", value)),
+	}
+}
+
+fn string(value: &NodeResult) -> Result<&str, String> {
+	match value {
+		NodeResult::String(str) => Ok(str),
+		_ => Err(expected("string", value)),
+	}
+}
+
+type List = Rc<RefCell<Vec<NodeResult>>>;
+type Map = Rc<RefCell<BTreeMap<String, NodeResult>>>;
+
+fn list(value: &NodeResult) -> Result<&List, String> {
+	match value {
+		NodeResult::List(list) => Ok(list),
+		_ => Err(expected("list", value)),
+	}
+}
+
+fn map(value: &NodeResult) -> Result<&Map, String> {
+	match value {
+		NodeResult::Map(map) => Ok(map),
+		_ => Err(expected("map", value)),
+	}
+}
+
+fn list_of(items: Vec<NodeResult>) -> NodeResult {
+	NodeResult::List(Rc::new(RefCell::new(items)))
+}
+
+fn print(args: &[NodeResult]) -> Result<NodeResult, String> {
+	print!("{}", args[0]);
+	io::stdout().flush().map_err(|err| err.to_string())?;
+
+	Ok(NodeResult::Nil)
+}
+
+fn println(args: &[NodeResult]) -> Result<NodeResult, String> {
+	println!("{}", args[0]);
+
+	Ok(NodeResult::Nil)
+}
+
+fn eprint(args: &[NodeResult]) -> Result<NodeResult, String> {
+	eprint!("{}", args[0]);
+
+	Ok(NodeResult::Nil)
+}
+
+fn len(args: &[NodeResult]) -> Result<NodeResult, String> {
+	let len = match &args[0] {
+		NodeResult::Map(map) => map.borrow().len() as i64,
+		NodeResult::Range(start, end) => end.saturating_sub(*start).max(0),
+		value => value.length().ok_or_else(|| {
+			format!("cannot take the length of {}", value.type_name())
+		})? as i64,
+	};
+
+	Ok(NodeResult::Int(len))
+}
+
+fn str(args: &[NodeResult]) -> Result<NodeResult, String> {
+	Ok(NodeResult::String(args[0].to_string()))
+}
+
+fn num(args: &[NodeResult]) -> Result<NodeResult, String> {
+	match &args[0] {
+		NodeResult::Int(_) | NodeResult::Float(_) => Ok(args[0].clone()),
+		// whole numbers become ints, anything else that parses a float
+		NodeResult::String(str) => {
+			let str = str.trim();
+			str.parse()
+				.map(NodeResult::Int)
+				.or_else(|_| str.parse().map(NodeResult::Float))
+				.map_err(|_| format!("cannot convert `{}` to a number", str))
+		}
+		value => {
+			Err(format!("cannot convert {} to a number", value.type_name()))
+		}
+	}
+}
+
+fn split(args: &[NodeResult]) -> Result<NodeResult, String> {
+	let (str, separator) = (string(&args[0])?, string(&args[1])?);
+	// an empty separator splits between every character
+	let parts = if separator.is_empty() {
+		str.chars().map(|char| char.to_string()).collect::<Vec<_>>()
+	} else {
+		str.split(separator).map(String::from).collect()
+	};
+
+	Ok(list_of(parts.into_iter().map(NodeResult::String).collect()))
+}
+
+fn join(args: &[NodeResult]) -> Result<NodeResult, String> {
+	let separator = string(&args[1])?;
+	let parts = list(&args[0])?
+		.borrow()
+		.iter()
+		.map(ToString::to_string)
+		.collect::<Vec<_>>();
+
+	Ok(NodeResult::String(parts.join(separator)))
+}
+
+fn trim(args: &[NodeResult]) -> Result<NodeResult, String> {
+	Ok(NodeResult::String(string(&args[0])?.trim().into()))
+}
+
+fn upper(args: &[NodeResult]) -> Result<NodeResult, String> {
+	Ok(NodeResult::String(string(&args[0])?.to_uppercase()))
+}
+
+fn lower(args: &[NodeResult]) -> Result<NodeResult, String> {
+	Ok(NodeResult::String(string(&args[0])?.to_lowercase()))
+}
+
+// substrings of strings, items of lists and keys of maps
+fn contains(args: &[NodeResult]) -> Result<NodeResult, String> {
+	let found = match &args[0] {
+		NodeResult::String(str) => str.contains(string(&args[1])?),
+		NodeResult::List(list) => list.borrow().contains(&args[1]),
+		NodeResult::Map(map) => map.borrow().contains_key(string(&args[1])?),
+		value => return Err(expected("string, list or map", value)),
+	};
+
+	Ok(NodeResult::Boolean(found))
+}
+
+fn replace(args: &[NodeResult]) -> Result<NodeResult, String> {
+	let str = string(&args[0])?;
+	let (from, to) = (string(&args[1])?, string(&args[2])?);
+
+	Ok(NodeResult::String(str.replace(from, to)))
+}
+
+fn push(args: &[NodeResult]) -> Result<NodeResult, String> {
+	list(&args[0])?.borrow_mut().push(args[1].clone());
+
+	Ok(NodeResult::Nil)
+}
+
+fn pop(args: &[NodeResult]) -> Result<NodeResult, String> {
+	list(&args[0])?
+		.borrow_mut()
+		.pop()
+		.ok_or_else(|| "cannot pop from an empty list".into())
+}
+
+fn keys(args: &[NodeResult]) -> Result<NodeResult, String> {
+	let keys = map(&args[0])?
+		.borrow()
+		.keys()
+		.map(|key| NodeResult::String(key.clone()))
+		.collect();
+
+	Ok(list_of(keys))
+}
+
+fn values(args: &[NodeResult]) -> Result<NodeResult, String> {
+	Ok(list_of(map(&args[0])?.borrow().values().cloned().collect()))
+}
+
+// sorts a list of numbers or of strings in place
+fn sort(args: &[NodeResult]) -> Result<NodeResult, String> {
+	let mut items = list(&args[0])?.borrow_mut();
+	for pair in items.windows(2) {
+		pair[0].compare(&pair[1], "sort").map_err(|_| {
+			format!(
+				"cannot sort a list of {} and {}",
+				pair[0].type_name(),
+				pair[1].type_name()
+			)
+		})?;
+	}
+	items.sort_by(|lhs, rhs| lhs.compare(rhs, "sort").unwrap());
+
+	Ok(NodeResult::Nil)
+}
+
+// the largest int not above a number
+fn floor(args: &[NodeResult]) -> Result<NodeResult, String> {
+	let num = number(&args[0])?.floor();
+	match &args[0] {
+		NodeResult::Int(_) => Ok(args[0].clone()),
+		// i64::MAX rounds up to 2^63 as a float, so the range excludes it
+		_ if (i64::MIN as f64..i64::MAX as f64).contains(&num) => {
+			Ok(NodeResult::Int(num as i64))
+		}
+		_ => Err(format!("{:?} does not fit in an int", num)),
+	}
+}
+
+fn sqrt(args: &[NodeResult]) -> Result<NodeResult, String> {
+	Ok(NodeResult::Float(number(&args[0])?.sqrt()))
+}
+
+// ints raised to non-negative ints stay ints
+fn pow(args: &[NodeResult]) -> Result<NodeResult, String> {
+	if let (NodeResult::Int(base), NodeResult::Int(exp)) = (&args[0], &args[1])
+	{
+		if *exp >= 0 {
+			return u32::try_from(*exp)
+				.ok()
+				.and_then(|exp| base.checked_pow(exp))
+				.map(NodeResult::Int)
+				.ok_or_else(|| {
+					format!(
+						"integer overflow: {} to the power of {} does not fit \
+						 in an int",
+						base, exp
+					)
+				});
+		}
+	}
+
+	Ok(NodeResult::Float(number(&args[0])?.powf(number(&args[1])?)))
+}
+
+fn min(args: &[NodeResult]) -> Result<NodeResult, String> {
+	let (lhs, rhs) = (number(&args[0])?, number(&args[1])?);
+	Ok(args[if rhs < lhs { 1 } else { 0 }].clone())
+}
+
+fn max(args: &[NodeResult]) -> Result<NodeResult, String> {
+	let (lhs, rhs) = (number(&args[0])?, number(&args[1])?);
+	Ok(args[if rhs > lhs { 1 } else { 0 }].clone())
+}
+
+thread_local! {
+	// xorshift state, seeded from the clock until a program picks a seed
+	static STATE: Cell<u64> = Cell::new(scramble(
+		SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map_or(0, |time| time.as_nanos() as u64),
+	));
+}
+
+// spreads the bits of `seed` out, since xorshift never leaves a zero state
+fn scramble(seed: u64) -> u64 { (seed ^ 0x9e37_79b9_7f4a_7c15).max(1) }
+
+// a number in `0..1`
+fn random(_: &[NodeResult]) -> Result<NodeResult, String> {
+	let mut state = STATE.with(Cell::get);
+	state ^= state << 13;
+	state ^= state >> 7;
+	state ^= state << 17;
+	STATE.with(|cell| cell.set(state));
+
+	// the top 53 bits fill an f64's mantissa exactly
+	Ok(NodeResult::Float(
+		(state >> 11) as f64 / (1u64 << 53) as f64,
+	))
+}
+
+// makes the numbers `random` returns repeatable
+fn seed(args: &[NodeResult]) -> Result<NodeResult, String> {
+	let seed = int(&args[0])?;
+	STATE.with(|cell| cell.set(scramble(seed as u64)));
+
+	Ok(NodeResult::Nil)
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{eval::Interpreter, lexer::Lexer, parser::Parser};
+
+	fn run(input: &str) -> Result<String, String> {
+		let input = format!("import {{ std }}\n{}", input);
+		let tree = Parser::new(Lexer::new(&input)).parse().unwrap();
+
+		Interpreter::new()
+			.run(&tree)
+			.map(|value| value.to_string())
+			.map_err(|err| err.message)
+	}
+
+	#[test]
+	fn std_conversions() {
+		assert_eq!(
+			run("[std.len(\"héllo\"), std.len([1, 2]), std.len({ a: 1 }), \
+			     std.len(2..5)]"),
+			Ok("[5, 2, 1, 3]".into())
+		);
+		assert_eq!(
+			run("std.str(1.5) + std.str([true])"),
+			Ok("1.5[true]".into())
+		);
+		assert_eq!(run("std.num(\" 42 \") + 1"), Ok("43".into()));
+		assert_eq!(
+			run("std.num(\"4x\")"),
+			Err("cannot convert `4x` to a number".into())
+		);
+		assert_eq!(
+			run("std.len(1)"),
+			Err("cannot take the length of int".into())
+		);
+	}
+
+	#[test]
+	fn std_strings() {
+		assert_eq!(
+			run("std.split(\"a,b,,c\", \",\")"),
+			Ok("[a, b, , c]".into())
+		);
+		assert_eq!(run("std.split(\"ab\", \"\")"), Ok("[a, b]".into()));
+		assert_eq!(
+			run("std.join([1, \"b\", true], \"-\")"),
+			Ok("1-b-true".into())
+		);
+		assert_eq!(
+			run("std.upper(std.trim(\"  hi \")) + std.lower(\"A\")"),
+			Ok("HIa".into())
+		);
+		assert_eq!(
+			run(
+				"[std.contains(\"hello\", \"ell\"), std.contains([1, 2], 3), \
+				 std.contains({ a: 1 }, \"a\")]"
+			),
+			Ok("[true, false, true]".into())
+		);
+		assert_eq!(
+			run("std.replace(\"a-b-c\", \"-\", \"+\")"),
+			Ok("a+b+c".into())
+		);
+		assert_eq!(
+			run("std.trim(1)"),
+			Err("expected string, found int".into())
+		);
+	}
+
+	#[test]
+	fn std_collections() {
+		assert_eq!(
+			run("let a = [3, 1]\nstd.push(a, 2)\nlet last = \
+			     std.pop(a)\nstd.push(a, 0)\nstd.sort(a)\n[a, last]"),
+			Ok("[[0, 1, 3], 2]".into())
+		);
+		assert_eq!(
+			run("let m = { b: 2, a: 1 }\n[std.keys(m), std.values(m)]"),
+			Ok("[[a, b], [1, 2]]".into())
+		);
+		assert_eq!(
+			run("let a = [\"b\", \"a\"]\nstd.sort(a)\na"),
+			Ok("[a, b]".into())
+		);
+		assert_eq!(
+			run("std.sort([1, \"a\"])"),
+			Err("cannot sort a list of int and string".into())
+		);
+		assert_eq!(
+			run("std.pop([])"),
+			Err("cannot pop from an empty list".into())
+		);
+	}
+
+	#[test]
+	fn std_math() {
+		assert_eq!(
+			run(
+				"let m = std.math\n[m.floor(2.7), m.sqrt(16), m.pow(2, 10), \
+				 m.min(1, 2), m.max(1, 2)]"
+			),
+			Ok("[2, 4.0, 1024, 1, 2]".into())
+		);
+		assert_eq!(
+			run("let m = std.math\nm.seed(7)\nlet a = [m.random(), \
+			     m.random()]\nm.seed(7)\nlet b = [m.random(), m.random()]\na \
+			     == b && a[0] != a[1] && a[0] >= 0 && a[0] < 1"),
+			Ok("true".into())
+		);
+		assert_eq!(
+			run("std.math.pow(2)"),
+			Err("`pow` takes 2 arguments but 1 was supplied".into())
+		);
+	}
+}
@@ -0,0 +1,208 @@
+use crate::lexer::{Lexer, Token, TokenKind};
+
+// Sits between the lexer and the parser, handing out only the tokens the
+// grammar cares about. Comments, and newlines that can't end a statement, are
+// trivia: they are skipped, but the ones before each token are kept, for the
+// parser to find doc comments in and for tools like a formatter that need to
+// put them back.
+#[derive(Clone)]
+pub struct TokenStream<'a> {
+	lexer: Lexer<'a>,
+	// the `(`, `[` and `{` still open, innermost last
+	groups: Vec<TokenKind>,
+	// whether the last token handed out was a binary operator, which needs
+	// an operand after it
+	continued: bool,
+	// the next token, once it has been peeked at
+	peeked: Option<Option<Token>>,
+	trivia: Vec<Token>,
+}
+
+impl<'a> TokenStream<'a> {
+	pub(crate) fn new(lexer: Lexer<'a>) -> TokenStream<'a> {
+		Self {
+			lexer,
+			groups: Vec::new(),
+			continued: false,
+			peeked: None,
+			trivia: Vec::new(),
+		}
+	}
+
+	// the next token, without handing it out
+	pub(crate) fn peek(&mut self) -> Option<&Token> {
+		if self.peeked.is_none() {
+			self.peeked = Some(self.scan());
+		}
+
+		self.peeked.as_ref().and_then(Option::as_ref)
+	}
+
+	// the trivia right before the token last peeked at or handed out, or
+	// after the last token once the input has run out
+	pub(crate) fn trivia(&self) -> &[Token] { &self.trivia }
+
+	// a closing bracket also closes anything left open inside its group, so
+	// one missing `)` doesn't leave the rest of the input inside parentheses
+	fn close(&mut self, open: TokenKind) {
+		if let Some(at) = self.groups.iter().rposition(|group| *group == open) {
+			self.groups.truncate(at);
+		}
+	}
+
+	// A newline inside `(...)` or `[...]`, or after a binary operator, can't
+	// end a statement, so an expression may go on over several lines. Blocks
+	// and maps hold statements and entries, which newlines still separate.
+	fn is_trivia(&self, token: &Token) -> bool {
+		match token.kind {
+			TokenKind::Comment(_) | TokenKind::DocComment(_) => true,
+			TokenKind::Delimeter if self.lexer.text(token.span) == "\n" => {
+				self.continued
+					|| matches!(
+						self.groups.last(),
+						Some(TokenKind::LParen | TokenKind::LBracket)
+					)
+			}
+			_ => false,
+		}
+	}
+
+	// the next token that isn't trivia, along with the trivia before it
+	fn scan(&mut self) -> Option<Token> {
+		self.trivia.clear();
+
+		loop {
+			let token = self.lexer.next()?;
+			if self.is_trivia(&token) {
+				self.trivia.push(token);
+				continue;
+			}
+
+			match token.kind {
+				TokenKind::LParen | TokenKind::LBracket | TokenKind::LBrace => {
+					self.groups.push(token.kind.clone())
+				}
+				TokenKind::RParen => self.close(TokenKind::LParen),
+				TokenKind::RBracket => self.close(TokenKind::LBracket),
+				TokenKind::RBrace => self.close(TokenKind::LBrace),
+				_ => {}
+			}
+			self.continued = token.is_bin_op();
+
+			return Some(token);
+		}
+	}
+}
+
+impl<'a> Iterator for TokenStream<'a> {
+	type Item = Token;
+
+	fn next(&mut self) -> Option<Token> {
+		match self.peeked.take() {
+			Some(token) => token,
+			None => self.scan(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn kinds(input: &str) -> Vec<TokenKind> {
+		TokenStream::new(Lexer::new(input))
+			.map(|token| token.kind)
+			.collect()
+	}
+
+	#[test]
+	fn stream_skips_comments() {
+		assert_eq!(
+			kinds("a // one\n/* two */ b /// three"),
+			vec![
+				TokenKind::Ident("a".into()),
+				TokenKind::Delimeter,
+				TokenKind::Ident("b".into()),
+			]
+		);
+	}
+
+	#[test]
+	fn stream_newlines_in_groups() {
+		assert_eq!(
+			kinds("f(\n\ta,\n\t[b,\n\tc]\n)\n{\n\td\n}"),
+			vec![
+				TokenKind::Ident("f".into()),
+				TokenKind::LParen,
+				TokenKind::Ident("a".into()),
+				TokenKind::Comma,
+				TokenKind::LBracket,
+				TokenKind::Ident("b".into()),
+				TokenKind::Comma,
+				TokenKind::Ident("c".into()),
+				TokenKind::RBracket,
+				TokenKind::RParen,
+				TokenKind::Delimeter,
+				TokenKind::LBrace,
+				TokenKind::Delimeter,
+				TokenKind::Ident("d".into()),
+				TokenKind::Delimeter,
+				TokenKind::RBrace,
+			]
+		);
+		// a block inside parentheses still separates its statements
+		assert_eq!(
+			kinds("(fun () {\n\ta\n})")[4..8],
+			[
+				TokenKind::LBrace,
+				TokenKind::Delimeter,
+				TokenKind::Ident("a".into()),
+				TokenKind::Delimeter,
+			]
+		);
+	}
+
+	#[test]
+	fn stream_newlines_after_operators() {
+		assert_eq!(
+			kinds("a +\n// comment\n\nb\nc;\n-d"),
+			vec![
+				TokenKind::Ident("a".into()),
+				TokenKind::Plus,
+				TokenKind::Ident("b".into()),
+				TokenKind::Delimeter,
+				TokenKind::Ident("c".into()),
+				TokenKind::Delimeter,
+				TokenKind::Delimeter,
+				TokenKind::Minus,
+				TokenKind::Ident("d".into()),
+			]
+		);
+	}
+
+	#[test]
+	fn stream_keeps_trivia() {
+		let mut stream =
+			TokenStream::new(Lexer::new("a = // one\nb /// two\n/* three */"));
+		let trivia = |stream: &TokenStream<'_>| {
+			stream
+				.trivia()
+				.iter()
+				.map(|token| token.kind.clone())
+				.collect::<Vec<_>>()
+		};
+
+		stream.nth(1);
+		assert_eq!(trivia(&stream), vec![]);
+		// peeking moves on to the trivia of the next token
+		assert_eq!(stream.peek().unwrap().kind, TokenKind::Ident("b".into()));
+		assert_eq!(
+			trivia(&stream),
+			vec![TokenKind::Comment("one".into()), TokenKind::Delimeter]
+		);
+		stream.nth(1);
+		assert_eq!(trivia(&stream), vec![TokenKind::DocComment("two".into())]);
+		assert_eq!(stream.next(), None);
+		assert_eq!(trivia(&stream), vec![TokenKind::Comment("three".into())]);
+	}
+}